@@ -1,11 +1,19 @@
+use crate::config;
+use crate::error::Error;
 use crate::http::{is_http_error, Client};
+use crate::statement::{self, Block, Lang, Statement};
 use anyhow::{anyhow, bail, Context as _, Result};
 use chrono::{DateTime, Utc};
 use itertools::Itertools as _;
+use once_cell::sync::Lazy;
 use regex::Regex;
 use scraper::{element_ref::ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use sha2::Digest as _;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::path::Path;
+use tracing::{debug, instrument};
 use url::Url;
 
 const ATCODER_ENDPOINT: &str = "https://atcoder.jp";
@@ -28,12 +36,323 @@ pub struct Problem {
     pub mle: String,
 }
 
+impl Problem {
+    pub fn full_url(&self) -> String {
+        format!("{}{}", ATCODER_ENDPOINT, self.url)
+    }
+
+    /// The last segment of the URL, such as `abc001_1`.
+    pub fn task_screen_name(&self) -> &str {
+        self.url.rsplit('/').next().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ContestSchedule {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl ContestSchedule {
+    pub fn started(&self) -> bool {
+        Utc::now() >= self.start
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContestStatus {
+    Running,
+    Upcoming,
+    Recent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContestType {
+    Algorithm,
+    Heuristic,
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum RatedRange {
+    Unrated,
+    All,
+    Range {
+        lower: Option<u32>,
+        upper: Option<u32>,
+    },
+}
+
+impl RatedRange {
+    fn from_str(s: &str) -> Option<Self> {
+        let s = s.trim();
+        match s {
+            "-" => return Some(RatedRange::Unrated),
+            "All" => return Some(RatedRange::All),
+            _ => (),
+        }
+        let (lower, upper) = s.split_once('~')?;
+        let bound = |s: &str| -> Option<Option<u32>> {
+            let s = s.trim();
+            if s.is_empty() {
+                Some(None)
+            } else {
+                s.parse().ok().map(Some)
+            }
+        };
+        Some(RatedRange::Range {
+            lower: bound(lower)?,
+            upper: bound(upper)?,
+        })
+    }
+}
+
+impl fmt::Display for RatedRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RatedRange::Unrated => write!(f, "-"),
+            RatedRange::All => write!(f, "All"),
+            RatedRange::Range { lower, upper } => {
+                if let Some(lower) = lower {
+                    write!(f, "{} ", lower)?;
+                }
+                write!(f, "~")?;
+                if let Some(upper) = upper {
+                    write!(f, " {}", upper)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContestSummary {
+    pub id: String,
+    pub title: String,
+    pub status: ContestStatus,
+    pub start: DateTime<Utc>,
+    pub duration_minutes: i64,
+    pub rated_range: RatedRange,
+    pub contest_type: ContestType,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Standings {
+    pub task_info: Vec<StandingsTask>,
+    pub standings_data: Vec<StandingsRow>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StandingsTask {
+    pub assignment: String,
+    pub task_screen_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StandingsRow {
+    pub rank: usize,
+    pub user_screen_name: String,
+    pub old_rating: i64,
+    pub is_rated: bool,
+    pub competitions: usize,
+    pub task_results: BTreeMap<String, TaskResult>,
+    pub total_result: TotalResult,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TaskResult {
+    pub count: usize,
+    pub failure: usize,
+    pub penalty: usize,
+    /// Score multiplied by 100.
+    pub score: i64,
+    /// Elapsed time in nanoseconds.
+    pub elapsed: i64,
+    pub pending: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TotalResult {
+    pub count: usize,
+    pub penalty: usize,
+    /// Score multiplied by 100.
+    pub score: i64,
+    /// Elapsed time in nanoseconds.
+    pub elapsed: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserProfile {
+    pub name: String,
+    pub rating: Option<i64>,
+    pub highest_rating: Option<i64>,
+    pub rank: Option<usize>,
+    pub competitions: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RatingHistoryEntry {
+    pub is_rated: bool,
+    pub place: usize,
+    pub old_rating: i64,
+    pub new_rating: i64,
+    pub performance: i64,
+    pub contest_screen_name: String,
+    pub contest_name: String,
+    pub end_time: DateTime<chrono::FixedOffset>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorialKind {
+    Official,
+    User,
+}
+
+#[derive(Debug, Clone)]
+pub struct EditorialLink {
+    pub kind: EditorialKind,
+    pub title: String,
+    /// Path on AtCoder (e.g. `/contests/abc200/editorial/1234`) or an external URL.
+    pub url: String,
+    pub author: Option<String>,
+}
+
+impl EditorialLink {
+    pub fn is_external(&self) -> bool {
+        !self.url.starts_with('/')
+    }
+
+    pub fn full_url(&self) -> String {
+        if self.is_external() {
+            self.url.clone()
+        } else {
+            format!("{}{}", ATCODER_ENDPOINT, self.url)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Clarification {
+    pub task: String,
+    pub question: String,
+    pub answer: String,
+    pub date: DateTime<Utc>,
+}
+
+impl Clarification {
+    pub fn answered(&self) -> bool {
+        !self.answer.is_empty()
+    }
+
+    /// Clarifications have no visible ID, so they are identified by their content.
+    pub fn id(&self) -> String {
+        let digest = sha2::Sha256::digest(
+            format!(
+                "{}\n{}\n{}",
+                self.task,
+                self.question,
+                self.date.timestamp()
+            )
+            .as_bytes(),
+        );
+        data_encoding::HEXLOWER.encode(&digest)[..16].to_owned()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Language {
+    pub id: String,
+    pub name: String,
+}
+
+/// Finds a language by its ID, its exact name, or a unique part of its name.
+pub fn find_language<'a>(languages: &'a [Language], spec: &str) -> Result<&'a Language> {
+    if let Some(lang) = languages
+        .iter()
+        .find(|l| l.id == spec || l.name.eq_ignore_ascii_case(spec))
+    {
+        return Ok(lang);
+    }
+
+    let spec_lower = spec.to_lowercase();
+    let candidates = languages
+        .iter()
+        .filter(|l| l.name.to_lowercase().contains(&spec_lower))
+        .collect::<Vec<_>>();
+
+    match *candidates {
+        [lang] => Ok(lang),
+        [] => bail!(
+            "Language `{}` is not available. Run `cargo atcoder languages` to see the choices",
+            spec
+        ),
+        _ => bail!(
+            "Language `{}` is ambiguous: {}",
+            spec,
+            candidates
+                .iter()
+                .map(|l| format!("`{}` ({})", l.name, l.id))
+                .join(", ")
+        ),
+    }
+}
+
+/// The language used when none is specified: the first Rust in the list.
+pub fn default_language(languages: &[Language]) -> Option<&Language> {
+    languages
+        .iter()
+        .find(|l| l.name.to_lowercase().starts_with("rust"))
+}
+
+/// The version of rustc in the name of a language, such as `1.70.0` in `Rust (rustc 1.70.0)`.
+pub fn rustc_version(language: &Language) -> Option<&str> {
+    static VERSION_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\d+\.\d+\.\d+").unwrap());
+    VERSION_RE.find(&language.name).map(|m| m.as_str())
+}
+
+struct SubmitForm {
+    task_screen_name: String,
+    languages: Vec<Language>,
+    csrf_token: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct TestCase {
     pub input: String,
     pub output: String,
 }
 
+/// A task page, from which both the samples and the statement are scraped.
+pub struct TaskPage {
+    doc: Html,
+}
+
+impl TaskPage {
+    pub fn test_cases(&self) -> Result<Vec<TestCase>> {
+        parse_test_cases(&self.doc)
+    }
+
+    pub fn statement(&self, problem: &Problem, lang: Lang) -> Result<Statement> {
+        Ok(Statement {
+            title: format!("{} - {}", problem.id, problem.name),
+            url: problem.full_url(),
+            time_limit: problem.tle.clone(),
+            memory_limit: problem.mle.clone(),
+            sections: statement::parse_sections(&self.doc, lang)?,
+        })
+    }
+}
+
 impl ContestInfo {
     pub fn problem(&self, id: &str) -> Option<&Problem> {
         self.problems
@@ -41,6 +360,13 @@ impl ContestInfo {
             .find(|p| p.id.to_lowercase() == id.to_lowercase())
     }
 
+    /// Finds a problem by its task screen name, such as `abc001_1`.
+    pub fn problem_by_task(&self, task_screen_name: &str) -> Option<&Problem> {
+        self.problems
+            .iter()
+            .find(|p| p.task_screen_name() == task_screen_name)
+    }
+
     pub fn problem_ids_lowercase(&self) -> Vec<String> {
         self.problems.iter().map(|p| p.id.to_lowercase()).collect()
     }
@@ -83,10 +409,7 @@ pub enum StatusCode {
 
 impl StatusCode {
     pub fn done(&self) -> bool {
-        match self {
-            StatusCode::Done(_) => true,
-            _ => false,
-        }
+        matches!(self, StatusCode::Done(_))
     }
 
     pub fn result_code(&self) -> Option<&ResultCode> {
@@ -148,11 +471,7 @@ impl ResultCode {
     }
 
     pub fn accepted(&self) -> bool {
-        use ResultCode::*;
-        match self {
-            Accepted => true,
-            _ => false,
-        }
+        matches!(self, ResultCode::Accepted)
     }
 }
 
@@ -171,23 +490,22 @@ impl StatusCode {
         // In progress, result code is as below:
         // 6/9 TLE
 
-        let re = Regex::new(r"^(\d+) */ *(\d+) *(.*)$").unwrap();
+        static PROGRESS_RE: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"^(\d+) */ *(\d+) *(.*)$").unwrap());
 
-        if let Some(caps) = re.captures(s) {
-            let cur = caps[1].parse().unwrap();
-            let total = caps[2].parse().unwrap();
+        if let Some(caps) = PROGRESS_RE.captures(s) {
+            let cur = caps[1].parse().ok()?;
+            let total = caps[2].parse().ok()?;
 
             let rest = caps[3].trim();
-            if rest == "" {
+            if rest.is_empty() {
                 return Some(Progress(cur, total, None));
             }
 
-            let code = Self::from_str(rest)?;
-            if let Done(code) = code {
-                return Some(Progress(cur, total, Some(code)));
-            } else {
-                panic!("Invalid result status code: `{}`", s);
-            }
+            return match Self::from_str(rest)? {
+                Done(code) => Some(Progress(cur, total, Some(code))),
+                _ => None,
+            };
         }
 
         Some(Done(match s {
@@ -204,51 +522,288 @@ impl StatusCode {
     }
 }
 
+#[derive(Default)]
+struct Samples {
+    inputs_ja: Vec<String>,
+    outputs_ja: Vec<String>,
+    inputs_en: Vec<String>,
+    outputs_en: Vec<String>,
+}
+
+impl Samples {
+    /// Returns `false` if `label` is not a sample heading.
+    fn push(&mut self, label: &str, pre: ElementRef) -> bool {
+        let v = if label.starts_with("入力例") {
+            &mut self.inputs_ja
+        } else if label.starts_with("出力例") {
+            &mut self.outputs_ja
+        } else if label.starts_with("Sample Input") {
+            &mut self.inputs_en
+        } else if label.starts_with("Sample Output") {
+            &mut self.outputs_en
+        } else {
+            return false;
+        };
+        // Old pages may have `<var>` and the like inside `pre`.
+        v.push(statement::sample_text(&pre.text().collect::<String>()));
+        true
+    }
+
+    fn into_test_cases(self) -> Option<Vec<TestCase>> {
+        let (inputs, outputs) =
+            if !self.inputs_ja.is_empty() && self.inputs_ja.len() == self.outputs_ja.len() {
+                (self.inputs_ja, self.outputs_ja)
+            } else if !self.inputs_en.is_empty() && self.inputs_en.len() == self.outputs_en.len() {
+                (self.inputs_en, self.outputs_en)
+            } else {
+                return None;
+            };
+
+        Some(
+            inputs
+                .into_iter()
+                .zip(outputs)
+                .map(|(input, output)| TestCase { input, output })
+                .collect(),
+        )
+    }
+
+    fn counts(&self) -> String {
+        format!(
+            "JA inputs: {}, JA outputs: {}, EN inputs: {}, EN outputs: {}",
+            self.inputs_ja.len(),
+            self.outputs_ja.len(),
+            self.inputs_en.len(),
+            self.outputs_en.len(),
+        )
+    }
+}
+
+fn parse_test_cases(doc: &Html) -> Result<Vec<TestCase>> {
+    let h3_sel = selector("h3");
+    let pre_sel = selector("pre");
+
+    // <section>
+    //     <h3>入力例 1</h3>
+    //     <pre>...</pre>
+    // </section>
+    let mut samples = Samples::default();
+    for r in doc.select(&h3_sel) {
+        let p = match r.parent().and_then(ElementRef::wrap) {
+            Some(p) if p.select(&h3_sel).count() == 1 => p,
+            _ => continue,
+        };
+        if let Some(pre) = p.select(&pre_sel).next() {
+            samples.push(r.text().collect::<String>().trim(), pre);
+        }
+    }
+    let counts = samples.counts();
+    if let Some(test_cases) = samples.into_test_cases() {
+        return Ok(test_cases);
+    }
+
+    // Old contests have various layouts, such as several samples in one
+    // `<div class="part">`, labels in `<p>` or `<h4>` instead of `<h3>`, and
+    // `pre` in a `<section>` next to the label. Each `pre` is labeled by the
+    // nearest text before it.
+    let mut samples = Samples::default();
+    for pre in doc.select(&pre_sel) {
+        if let Some(label) = legacy_sample_label(pre, &pre_sel) {
+            samples.push(&label, pre);
+        }
+    }
+    let legacy_counts = samples.counts();
+    samples.into_test_cases().with_context(|| {
+        format!(
+            "Could not scrape sample test cases ({}; in the legacy layout: {})",
+            counts, legacy_counts
+        )
+    })
+}
+
+fn legacy_sample_label(pre: ElementRef, pre_sel: &Selector) -> Option<String> {
+    let mut node = pre;
+    loop {
+        for sib in node.prev_siblings() {
+            let (text, has_pre) = match ElementRef::wrap(sib) {
+                Some(e) => (
+                    e.text().collect::<String>(),
+                    e.value().name() == "pre" || e.select(pre_sel).next().is_some(),
+                ),
+                None => (sib.value().as_text().map_or("", |t| t).to_owned(), false),
+            };
+            let text = text.trim();
+            if ["入力例", "出力例", "Sample Input", "Sample Output"]
+                .iter()
+                .any(|label| text.starts_with(label))
+            {
+                return Some(text.to_owned());
+            }
+            // Another sample or a format.
+            if has_pre {
+                return None;
+            }
+        }
+
+        node = ElementRef::wrap(node.parent()?)?;
+        if node.value().id() == Some("task-statement") || node.value().name() == "body" {
+            return None;
+        }
+    }
+}
+
+/// Parses a selector written in the source.
+pub fn selector(selectors: &str) -> Selector {
+    Selector::parse(selectors)
+        .unwrap_or_else(|err| panic!("invalid selector `{}`: {:?}", selectors, err))
+}
+
+fn find_csrf_token(doc: &Html) -> Option<&str> {
+    doc.select(&selector("input[name=\"csrf_token\"]"))
+        .next()?
+        .value()
+        .attr("value")
+}
+
 impl AtCoder {
     pub fn new(session_file: &Path) -> Result<AtCoder> {
         Ok(Self {
-            client: Client::new(session_file, ATCODER_ENDPOINT)?,
+            client: Client::new(
+                session_file,
+                ATCODER_ENDPOINT,
+                &config::read_config()?.network,
+            )?,
         })
     }
 
     async fn check_login(&self) -> Result<()> {
-        let _ = self
-            .username()
-            .await?
-            .with_context(|| "You are not logged in. Please login first.")?;
+        if self.username().await?.is_none() {
+            bail!(Error::NotLoggedIn);
+        }
         Ok(())
     }
 
+    #[instrument(skip(self))]
     pub async fn username(&self) -> Result<Option<String>> {
         let doc = self.http_get("/").await?;
         let doc = Html::parse_document(&doc);
 
-        let r = doc
-            .select(&Selector::parse("li a[href^=\"/users/\"]").unwrap())
-            .next();
+        Ok(doc
+            .select(&selector("li a[href^=\"/users/\"]"))
+            .next()
+            .and_then(|r| r.value().attr("href")?.strip_prefix("/users/"))
+            .map(ToOwned::to_owned))
+    }
+
+    pub async fn user_profile(&self, user: &str) -> Result<UserProfile> {
+        let doc = self
+            .retrieve_public_text(&format!("/users/{}?lang=en", user), || {
+                format!("User `{}` does not exist", user)
+            })
+            .await?;
+        let doc = Html::parse_document(&doc);
 
-        if r.is_none() {
-            return Ok(None);
+        // <table class="dl-table mt-2">
+        //     <tr><th class="no-break">Rank</th><td>1234th</td></tr>
+        //     <tr><th class="no-break">Rating</th><td><span class='user-blue'>1650</span></td></tr>
+        //     <tr><th class="no-break">Highest Rating</th><td><span class='user-blue'>1700</span> ... </td></tr>
+        //     <tr><th class="no-break">Rated Matches <span ...></span></th><td>30</td></tr>
+        //     <tr><th class="no-break">Last Competed</th><td>2020/01/19</td></tr>
+        // </table>
+        let mut fields = BTreeMap::new();
+        let (sel_th, sel_td) = (selector("th"), selector("td"));
+        for tr in doc.select(&selector("table.dl-table tr")) {
+            let th = tr.select(&sel_th).next();
+            let td = tr.select(&sel_td).next();
+            if let (Some(th), Some(td)) = (th, td) {
+                fields.insert(
+                    th.text().collect::<String>().trim().to_owned(),
+                    td.text().collect::<String>(),
+                );
+            }
         }
 
-        Ok(Some(
-            r.unwrap().value().attr("href").unwrap()[7..].to_owned(),
-        ))
+        let leading_number = |key: &str| -> Option<i64> {
+            fields
+                .get(key)?
+                .trim()
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect::<String>()
+                .parse()
+                .ok()
+        };
+
+        Ok(UserProfile {
+            name: user.to_owned(),
+            rating: leading_number("Rating"),
+            highest_rating: leading_number("Highest Rating"),
+            rank: leading_number("Rank").map(|r| r as usize),
+            competitions: leading_number("Rated Matches").unwrap_or(0) as usize,
+        })
+    }
+
+    pub async fn rating_history(&self, user: &str) -> Result<Vec<RatingHistoryEntry>> {
+        let json = self
+            .retrieve_public_text(&format!("/users/{}/history/json", user), || {
+                format!("User `{}` does not exist", user)
+            })
+            .await?;
+        serde_json::from_str(&json).with_context(|| "Failed to parse the rating history")
+    }
+
+    /// Posts the logout form and returns whether the server invalidated the session.
+    #[instrument(skip(self))]
+    pub async fn logout(&self) -> Result<bool> {
+        let doc = self.http_get("/").await?;
+        let doc = Html::parse_document(&doc);
+
+        // <form method="POST" name="form_logout" action="/logout?continue=...">
+        //     <input type="hidden" name="csrf_token" value="..." />
+        // </form>
+        let csrf_token = doc
+            .select(&selector(
+                "form[action^=\"/logout\"] input[name=\"csrf_token\"]",
+            ))
+            .next()
+            .and_then(|r| r.value().attr("value"))
+            .ok_or_else(|| Error::layout_changed("the logout form", &doc.root_element().html()))?;
+
+        let _ = self
+            .http_post_form("/logout", &[("csrf_token", csrf_token)])
+            .await?;
+
+        Ok(self.username().await?.is_none())
+    }
+
+    /// Imports a `REVEL_SESSION` cookie and returns the user name it is logged in as.
+    /// The cookie is checked on a throwaway session first, so an invalid one is never saved.
+    #[instrument(skip(self, revel_session))]
+    pub async fn login_with_cookie(&self, revel_session: &str) -> Result<String> {
+        let cookie = format!("REVEL_SESSION={}", revel_session);
+
+        let probe = AtCoder {
+            client: Client::in_memory(ATCODER_ENDPOINT, &config::read_config()?.network)?,
+        };
+        probe.client.set_cookie(&cookie)?;
+        let username = probe
+            .username()
+            .await?
+            .with_context(|| "The cookie is invalid or expired")?;
+
+        self.client.set_cookie(&cookie)?;
+        self.client.save_session()?;
+        Ok(username)
     }
 
+    #[instrument(skip(self, password))]
     pub async fn login(&self, username: &str, password: &str) -> Result<()> {
         let document = self.http_get("/login").await?;
         let document = Html::parse_document(&document);
 
-        let csrf_token = document
-            .select(&Selector::parse("input[name=\"csrf_token\"]").unwrap())
-            .next()
-            .with_context(|| "cannot find csrf_token")?;
-
-        let csrf_token = csrf_token
-            .value()
-            .attr("value")
-            .with_context(|| "cannot find csrf_token")?;
+        let csrf_token = find_csrf_token(&document).ok_or_else(|| {
+            Error::layout_changed("the login form", &document.root_element().html())
+        })?;
 
         let res = self
             .http_post_form(
@@ -268,13 +823,13 @@ impl AtCoder {
         //   ...
         //   {{error message}}
         // </div>
-        if let Some(err) = res
-            .select(&Selector::parse("div.alert-danger").unwrap())
-            .next()
-        {
+        if let Some(err) = res.select(&selector("div.alert-danger")).next() {
             bail!(
                 "Login failed: {}",
-                err.last_child().unwrap().value().as_text().unwrap().trim()
+                err.text()
+                    .collect::<String>()
+                    .trim_start_matches('×')
+                    .trim()
             );
         }
 
@@ -283,28 +838,93 @@ impl AtCoder {
         //     ...
         //     ようこそ、tanakh さん。
         // </div>
-        if res
-            .select(&Selector::parse("div.alert-success").unwrap())
-            .next()
-            .is_some()
-        {
+        if res.select(&selector("div.alert-success")).next().is_some() {
             return Ok(());
         }
 
         Err(anyhow!("Login failed: Unknown error"))
     }
 
+    /// Returns `true` if the contest top page offers a registration form.
+    pub async fn needs_registration(&self, contest_id: &str) -> Result<bool> {
+        let doc = self.http_get(&format!("/contests/{}", contest_id)).await?;
+        Ok(Html::parse_document(&doc)
+            .select(&selector("form[action$=\"/register\"]"))
+            .next()
+            .is_some())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn register(&self, contest_id: &str) -> Result<()> {
+        self.check_login().await?;
+
+        let doc = self.http_get(&format!("/contests/{}", contest_id)).await?;
+        let csrf_token = {
+            let doc = Html::parse_document(&doc);
+
+            // <form method="POST" action="/contests/abc152/register">
+            //     <input type="hidden" name="csrf_token" value="..." />
+            //     <button type="submit" class="btn btn-lg btn-primary">参加登録</button>
+            // </form>
+            let form = doc
+                .select(&selector("form[action$=\"/register\"]"))
+                .next()
+                .with_context(|| {
+                    format!(
+                        "Cannot register for `{}`: already registered, or registration is closed",
+                        contest_id
+                    )
+                })?;
+
+            form.select(&selector("input[name=\"csrf_token\"]"))
+                .next()
+                .and_then(|input| input.value().attr("value"))
+                .ok_or_else(|| Error::layout_changed("the registration form", &form.html()))?
+                .to_owned()
+        };
+
+        let res = self
+            .http_post_form(
+                &format!("/contests/{}/register", contest_id),
+                &[("csrf_token", &csrf_token)],
+            )
+            .await?;
+
+        if let Some(err) = Html::parse_document(&res)
+            .select(&selector("div.alert-danger"))
+            .next()
+        {
+            bail!(
+                "Registration failed: {}",
+                err.text()
+                    .collect::<String>()
+                    .trim_start_matches('×')
+                    .trim()
+            );
+        }
+
+        if self.needs_registration(contest_id).await? {
+            bail!("Registration failed: Unknown error");
+        }
+        Ok(())
+    }
+
     pub async fn problem_ids_from_score_table(
         &self,
         contest_id: &str,
     ) -> Result<Option<Vec<String>>> {
         let doc = self.http_get(&format!("/contests/{}", contest_id)).await?;
+        let (sel_th, sel_tr, sel_td) = (
+            selector("thead > tr > th"),
+            selector("tbody > tr"),
+            selector("td"),
+        );
 
         Html::parse_document(&doc)
-            .select(&Selector::parse("#contest-statement > .lang > .lang-ja table").unwrap())
+            .select(&selector("#contest-statement > .lang > .lang-ja table"))
             .filter(|table| {
                 let header = table
-                    .select(&Selector::parse("thead > tr > th").unwrap())
+                    .select(&sel_th)
                     .flat_map(|r| r.text())
                     .collect::<Vec<_>>();
                 header == ["Task", "Score"] || header == ["問題", "点数"]
@@ -313,15 +933,15 @@ impl AtCoder {
             .ok()
             .map(|table| {
                 table
-                    .select(&Selector::parse("tbody > tr").unwrap())
+                    .select(&sel_tr)
                     .map(|tr| {
                         let text = tr
-                            .select(&Selector::parse("td").unwrap())
+                            .select(&sel_td)
                             .flat_map(|r| r.text())
                             .collect::<Vec<_>>();
                         match text.len() {
                             2 => Ok(text[0].to_owned()),
-                            _ => Err(anyhow!("could not parse the table")),
+                            _ => Err(Error::layout_changed("the score table", &tr.html()).into()),
                         }
                     })
                     .collect()
@@ -329,233 +949,487 @@ impl AtCoder {
             .transpose()
     }
 
-    pub async fn contest_info(&self, contest_id: &str) -> Result<ContestInfo> {
+    pub async fn contests(&self) -> Result<Vec<ContestSummary>> {
+        let doc = self.http_get("/contests/?lang=en").await?;
+        let doc = Html::parse_document(&doc);
+
+        let mut ret = vec![];
+        let (sel_td, sel_link, sel_type) = (
+            selector("td"),
+            selector("a[href^=\"/contests/\"]"),
+            selector("span[title]"),
+        );
+
+        for (status, table_id) in &[
+            (ContestStatus::Running, "contest-table-action"),
+            (ContestStatus::Upcoming, "contest-table-upcoming"),
+            (ContestStatus::Recent, "contest-table-recent"),
+        ] {
+            let sel = selector(&format!("#{} table tbody tr", table_id));
+
+            for r in doc.select(&sel) {
+                // <td class="text-center"><a href='http://www.timeanddate.com/...'><time class='fixtime-full'>2020-01-19 21:00:00+0900</time></a></td>
+                // <td>
+                //   <span aria-hidden='true' data-toggle='tooltip' data-placement='top' title="Algorithm">Ⓐ</span>
+                //   <span class="user-blue">◉</span>
+                //   <a href="/contests/abc152">AtCoder Beginner Contest 152</a>
+                // </td>
+                // <td class="text-center">01:40</td>
+                // <td class="text-center"> ~ 1999</td>
+
+                let res = (|| -> Option<ContestSummary> {
+                    let tds = r.select(&sel_td).collect_vec();
+                    let (start, title, duration, rated_range) = match *tds {
+                        [a, b, c, d, ..] => (a, b, c, d),
+                        _ => return None,
+                    };
+
+                    let start = start.text().collect::<String>();
+                    let start = DateTime::parse_from_str(start.trim(), "%Y-%m-%d %H:%M:%S%z")
+                        .ok()?
+                        .into();
+
+                    let link = title.select(&sel_link).next()?;
+                    let id = link.value().attr("href")?["/contests/".len()..].to_owned();
+                    let contest_type = match title
+                        .select(&sel_type)
+                        .next()
+                        .and_then(|span| span.value().attr("title"))
+                    {
+                        Some("Algorithm") => ContestType::Algorithm,
+                        Some("Heuristic") => ContestType::Heuristic,
+                        other => ContestType::Other(other.unwrap_or_default().to_owned()),
+                    };
+
+                    let duration = duration.text().collect::<String>();
+                    let (hours, minutes) = duration.trim().split_once(':')?;
+                    let duration_minutes =
+                        hours.parse::<i64>().ok()? * 60 + minutes.parse::<i64>().ok()?;
+
+                    Some(ContestSummary {
+                        id,
+                        title: link.text().collect::<String>().trim().to_owned(),
+                        status: *status,
+                        start,
+                        duration_minutes,
+                        rated_range: RatedRange::from_str(&rated_range.text().collect::<String>())?,
+                        contest_type,
+                    })
+                })();
+
+                ret.push(res.ok_or_else(|| Error::layout_changed("the contest list", &r.html()))?);
+            }
+        }
+
+        Ok(ret)
+    }
+
+    pub async fn contest_schedule(&self, contest_id: &str) -> Result<ContestSchedule> {
         let doc = self
-            .retrieve_text_or_error_message(&format!("/contests/{}/tasks", contest_id), || {
-                format!(
-                    "You are not participating in `{}`, or it does not yet exist",
-                    contest_id,
-                )
+            .retrieve_public_text(&format!("/contests/{}", contest_id), || {
+                format!("Contest `{}` does not exist", contest_id)
             })
             .await?;
 
+        // <small class="contest-duration">
+        //     Contest Duration:
+        //     <a href='...'><time class='fixtime fixtime-full'>2020-01-19 21:00:00+0900</time></a>
+        //     ~
+        //     <a href='...'><time class='fixtime fixtime-full'>2020-01-19 22:40:00+0900</time></a>
+        //     (local time)
+        //     (100 minutes)
+        // </small>
+        let times = Html::parse_document(&doc)
+            .select(&selector(".contest-duration time"))
+            .map(|t| {
+                let t = t.text().collect::<String>();
+                DateTime::parse_from_str(t.trim(), "%Y-%m-%d %H:%M:%S%z")
+                    .map(Into::into)
+                    .with_context(|| format!("could not parse the contest time: `{}`", t))
+            })
+            .collect::<Result<Vec<DateTime<Utc>>>>()?;
+
+        match *times {
+            [start, end] => Ok(ContestSchedule { start, end }),
+            _ => bail!(Error::layout_changed(
+                "the contest duration",
+                &format!(
+                    "{} time(s) found in `/contests/{}`",
+                    times.len(),
+                    contest_id
+                ),
+            )),
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn contest_info(&self, contest_id: &str) -> Result<ContestInfo> {
+        self.fetch_contest_info(contest_id, false).await
+    }
+
+    /// Like [`contest_info`](Self::contest_info), but also retries while the task list is not
+    /// found, since it may be unavailable for a few seconds right after the contest starts.
+    #[instrument(skip(self))]
+    pub async fn contest_info_after_start(&self, contest_id: &str) -> Result<ContestInfo> {
+        self.fetch_contest_info(contest_id, true).await
+    }
+
+    async fn fetch_contest_info(&self, contest_id: &str, until_found: bool) -> Result<ContestInfo> {
+        let path = format!("/contests/{}/tasks", contest_id);
+        let doc = if until_found {
+            self.http_get_until_found(&path).await
+        } else {
+            self.http_get(&path).await
+        };
+        let doc = match doc {
+            Err(err) if is_http_error(&err, reqwest::StatusCode::NOT_FOUND) => {
+                // The task list of an upcoming contest is hidden even from logged-in users.
+                if let Ok(schedule) = self.contest_schedule(contest_id).await {
+                    if !schedule.started() {
+                        return Err(err.context(Error::ContestNotStarted {
+                            contest_id: contest_id.to_owned(),
+                            start: schedule.start,
+                        }));
+                    }
+                }
+                if self.username().await?.is_none() {
+                    return Err(err.context(Error::NotLoggedIn));
+                }
+                return Err(err.context(format!(
+                    "You are not participating in `{}`, or it does not yet exist",
+                    contest_id,
+                )));
+            }
+            doc => doc?,
+        };
+
         let doc = Html::parse_document(&doc);
-        let sel_problem = Selector::parse("table tbody tr").unwrap();
+        let (sel_problem, sel_td, sel_a) =
+            (selector("table tbody tr"), selector("td"), selector("a"));
 
         let mut problems = vec![];
 
         for row in doc.select(&sel_problem) {
-            let sel_td = Selector::parse("td").unwrap();
-            let mut it = row.select(&sel_td);
-            let c1 = it.next().unwrap();
-            let c2 = it.next().unwrap();
-            let c3 = it.next().unwrap();
-            let c4 = it.next().unwrap();
-
-            let id = c1
-                .select(&Selector::parse("a").unwrap())
-                .next()
-                .unwrap()
-                .inner_html();
-
-            let name = c2
-                .select(&Selector::parse("a").unwrap())
-                .next()
-                .unwrap()
-                .inner_html();
+            let problem = (|| -> Option<Problem> {
+                let mut it = row.select(&sel_td);
+                let (c1, c2, c3, c4) = (it.next()?, it.next()?, it.next()?, it.next()?);
+
+                let id = c1.select(&sel_a).next()?.inner_html();
+                let link = c2.select(&sel_a).next()?;
+                let name = link.inner_html();
+                let url = link.value().attr("href")?;
+
+                let tle = c3.inner_html();
+                let mle = c4.inner_html();
+
+                Some(Problem {
+                    id: id.trim().to_owned(),
+                    name: name.trim().to_owned(),
+                    url: url.trim().to_owned(),
+                    tle: tle.trim().to_owned(),
+                    mle: mle.trim().to_owned(),
+                })
+            })()
+            .ok_or_else(|| Error::layout_changed("the task list", &row.html()))?;
 
-            let url = c2
-                .select(&Selector::parse("a").unwrap())
-                .next()
-                .unwrap()
-                .value()
-                .attr("href")
-                .unwrap();
-
-            let tle = c3.inner_html();
-            let mle = c4.inner_html();
-
-            problems.push(Problem {
-                id: id.trim().to_owned(),
-                name: name.trim().to_owned(),
-                url: url.trim().to_owned(),
-                tle: tle.trim().to_owned(),
-                mle: mle.trim().to_owned(),
-            });
+            problems.push(problem);
         }
 
         Ok(ContestInfo { problems })
     }
 
+    #[instrument(skip(self))]
+    pub async fn task_page(&self, problem: &Problem) -> Result<TaskPage> {
+        let doc = self.http_get(&problem.url).await?;
+        Ok(TaskPage {
+            doc: Html::parse_document(&doc),
+        })
+    }
+
     pub async fn test_cases(&self, problem_url: &str) -> Result<Vec<TestCase>> {
         let doc = self.http_get(problem_url).await?;
+        let doc = Html::parse_document(&doc);
+        parse_test_cases(&doc)
+    }
+
+    pub async fn problem_statement(&self, problem: &Problem, lang: Lang) -> Result<Statement> {
+        self.task_page(problem).await?.statement(problem, lang)
+    }
 
+    pub async fn editorials(&self, problem: &Problem) -> Result<Vec<EditorialLink>> {
+        let doc = self
+            .retrieve_text_or_error_message(&format!("{}/editorial", problem.url), || {
+                format!("Could not find editorials of `{}`", problem.id)
+            })
+            .await?;
         let doc = Html::parse_document(&doc);
 
-        let h3_sel = Selector::parse("h3").unwrap();
-
-        let mut inputs_ja = vec![];
-        let mut outputs_ja = vec![];
-        let mut inputs_en = vec![];
-        let mut outputs_en = vec![];
-
-        for r in doc.select(&h3_sel) {
-            let p = ElementRef::wrap(r.parent().unwrap()).unwrap();
-            let label = p.select(&h3_sel).next().unwrap().inner_html();
-            let label = label.trim();
-            // dbg!(r.parent().unwrap().first_child().unwrap().value());
-
-            // let label = r
-            //     .prev_sibling()
-            //     .unwrap()
-            //     .first_child()
-            //     .unwrap()
-            //     .value()
-            //     .as_text()
-            //     .unwrap();
-
-            let f = || {
-                p.select(&Selector::parse("pre").unwrap())
-                    .next()
-                    .unwrap()
-                    .text()
-                    .exactly_one()
-                    .map(|s| s.trim().to_owned())
-                    .unwrap_or_default()
+        // <h3>公式解説</h3>
+        // <ul>
+        //     <li>
+        //         <span class="label label-primary" ...>日本語</span>
+        //         <a href="/contests/abc200/editorial/1234">解説</a>
+        //         by <a href="/users/writer" class="username">...</a>
+        //     </li>
+        // </ul>
+        // <h3>ユーザ解説</h3>
+        // ...
+        let root = doc
+            .select(&selector("#main-container"))
+            .next()
+            .unwrap_or_else(|| doc.root_element());
+        let (sel_li, sel_link, sel_author) = (
+            selector("li"),
+            selector("a[href]"),
+            selector("a[href^=\"/users/\"]"),
+        );
+
+        let mut ret = vec![];
+
+        for heading in root.select(&selector("h2, h3, h4")) {
+            let text = heading.text().collect::<String>();
+            let kind = if text.contains("公式解説") || text.contains("Official Editorial") {
+                EditorialKind::Official
+            } else if text.contains("ユーザ解説") || text.contains("User Editorial") {
+                EditorialKind::User
+            } else {
+                continue;
             };
-            if label.starts_with("入力例") {
-                inputs_ja.push(f());
-            }
-            if label.starts_with("出力例") {
-                outputs_ja.push(f());
-            }
 
-            if label.starts_with("Sample Input") {
-                inputs_en.push(f());
-            }
-            if label.starts_with("Sample Output") {
-                outputs_en.push(f());
+            let lists = heading
+                .next_siblings()
+                .filter_map(ElementRef::wrap)
+                .take_while(|e| !matches!(e.value().name(), "h2" | "h3" | "h4"))
+                .filter(|e| e.value().name() == "ul");
+
+            for li in lists.flat_map(|ul| ul.select(&sel_li).collect_vec()) {
+                let link = li.select(&sel_link).find(|a| {
+                    !a.value()
+                        .attr("href")
+                        .unwrap_or_default()
+                        .starts_with("/users/")
+                });
+                let link = match link {
+                    Some(link) => link,
+                    None => continue,
+                };
+                let author = li
+                    .select(&sel_author)
+                    .next()
+                    .map(|a| a.text().collect::<String>().trim().to_owned());
+
+                ret.push(EditorialLink {
+                    kind,
+                    title: link.text().collect::<String>().trim().to_owned(),
+                    url: link.value().attr("href").unwrap_or_default().to_owned(),
+                    author,
+                });
             }
         }
 
-        let (inputs, outputs) = if !inputs_ja.is_empty() && inputs_ja.len() == outputs_ja.len() {
-            (inputs_ja, outputs_ja)
-        } else if !inputs_en.is_empty() && inputs_en.len() == outputs_en.len() {
-            (inputs_en, outputs_en)
-        } else {
-            bail!(
-                "Could not scrape sample test cases (JA inputs: {}, JA outputs: {}, EN inputs: \
-                 {}, EN outputs: {})",
-                inputs_ja.len(),
-                outputs_ja.len(),
-                inputs_en.len(),
-                outputs_en.len(),
-            );
-        };
+        Ok(ret)
+    }
 
-        let mut ret = vec![];
-        for i in 0..inputs.len() {
-            ret.push(TestCase {
-                input: inputs[i].clone(),
-                output: outputs[i].clone(),
-            });
+    pub async fn editorial_content(&self, link: &EditorialLink) -> Result<Vec<Block>> {
+        if link.is_external() {
+            bail!("`{}` is not hosted on AtCoder", link.url);
         }
-        Ok(ret)
+
+        let doc = self.http_get(&link.url).await?;
+        let doc = Html::parse_document(&doc);
+
+        // KaTeX renders the math only in `#editorial`.
+        if let Some(content) = doc.select(&selector("#editorial")).next() {
+            return Ok(statement::parse_katex_blocks(content));
+        }
+        let content = doc
+            .select(&selector("#main-container"))
+            .next()
+            .ok_or_else(|| Error::layout_changed("the editorial", &doc.root_element().html()))?;
+
+        Ok(statement::parse_blocks(content))
     }
 
-    pub async fn submit(
-        &self,
-        contest_id: &str,
-        problem_id: &str,
-        source_code: &str,
-    ) -> Result<()> {
+    pub async fn languages(&self, contest_id: &str, problem: &Problem) -> Result<Vec<Language>> {
         self.check_login().await?;
+        Ok(self.submit_form(contest_id, problem).await?.languages)
+    }
 
+    #[instrument(skip(self))]
+    async fn submit_form(&self, contest_id: &str, problem: &Problem) -> Result<SubmitForm> {
         let doc = self
             .http_get(&format!("/contests/{}/submit", contest_id))
             .await?;
+        let doc = Html::parse_document(&doc);
 
-        let (task_screen_name, language_id, language_name, csrf_token) = {
-            let doc = Html::parse_document(&doc);
+        let options = doc
+            .select(&selector("select[name=\"data.TaskScreenName\"] option"))
+            .collect_vec();
+        if options.is_empty() {
+            if self.needs_registration(contest_id).await? {
+                bail!(Error::NotRegistered {
+                    contest_id: contest_id.to_owned(),
+                });
+            }
+            bail!(Error::layout_changed(
+                "the submit form",
+                &doc.root_element().html()
+            ));
+        }
 
-            let task_screen_name = (|| {
-                for r in doc.select(
-                    &Selector::parse("select[name=\"data.TaskScreenName\"] option").unwrap(),
-                ) {
-                    if r.inner_html()
-                        .trim()
-                        .split_whitespace()
-                        .next()
-                        .unwrap()
-                        .to_lowercase()
-                        .starts_with(&problem_id.to_lowercase())
-                    {
-                        return Ok(r.value().attr("value").unwrap());
-                    }
-                }
-                Err(anyhow!("Problem not found: {}", problem_id))
-            })()?;
-
-            let (language_id, language_name) = (|| {
-                for r in doc.select(
-                    &Selector::parse(&format!(
-                        "div[id=\"select-lang-{}\"] select option",
-                        &task_screen_name
-                    ))
-                    .unwrap(),
-                ) {
-                    if r.inner_html()
-                        .trim()
-                        .split_whitespace()
-                        .next()
-                        .unwrap_or("")
-                        .to_lowercase()
-                        .starts_with("rust")
-                    {
-                        return Ok((r.value().attr("value").unwrap(), r.inner_html()));
-                    }
-                }
-                Err(anyhow!(
-                    "Rust seems to be not available in problem {}...",
-                    problem_id
-                ))
-            })()?;
+        let task_screen_name = problem.task_screen_name();
+        if !options
+            .iter()
+            .any(|r| r.value().attr("value") == Some(task_screen_name))
+        {
+            bail!(Error::ProblemNotFound {
+                contest_id: contest_id.to_owned(),
+                problem_id: problem.id.clone(),
+            });
+        }
 
-            let csrf_token = doc
-                .select(&Selector::parse("input[name=\"csrf_token\"]").unwrap())
-                .next()
-                .unwrap()
-                .value()
-                .attr("value")
-                .unwrap();
-
-            (
-                task_screen_name.to_owned(),
-                language_id.to_owned(),
-                language_name,
-                csrf_token.to_owned(),
-            )
+        // <div id="select-lang-abc152_a" ...>
+        //     <select class="form-control" data-placeholder="-" name="data.LanguageId" ...>
+        //         <option></option>
+        //         <option value="4050" data-mime="text/x-rustsrc">Rust (rustc 1.70.0)</option>
+        //         ...
+        //     </select>
+        // </div>
+        let select_lang_id = format!("select-lang-{}", task_screen_name);
+        let sel_option = selector("select option[value]");
+        let languages = doc
+            .select(&selector("div[id^=\"select-lang-\"]"))
+            .filter(|div| div.value().id() == Some(&*select_lang_id))
+            .flat_map(|div| div.select(&sel_option).collect_vec())
+            .filter_map(|r| {
+                let id = r.value().attr("value")?;
+                (!id.is_empty()).then(|| Language {
+                    id: id.to_owned(),
+                    name: r.text().collect::<String>().trim().to_owned(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let csrf_token = find_csrf_token(&doc)
+            .ok_or_else(|| Error::layout_changed("the submit form", &doc.root_element().html()))?;
+
+        debug!(
+            task_screen_name,
+            languages = languages.len(),
+            "parsed the submit form"
+        );
+
+        Ok(SubmitForm {
+            task_screen_name: task_screen_name.to_owned(),
+            languages,
+            csrf_token: csrf_token.to_owned(),
+        })
+    }
+
+    /// Submits the source code. If `language` is `None`, the first Rust in the list is used.
+    /// The login is expected to be checked by the caller beforehand.
+    #[instrument(skip(self, source_code))]
+    pub async fn submit(
+        &self,
+        contest_id: &str,
+        problem: &Problem,
+        source_code: &str,
+        language: Option<&str>,
+    ) -> Result<()> {
+        let form = self.submit_form(contest_id, problem).await?;
+
+        let language = match language {
+            Some(spec) => find_language(&form.languages, spec)?,
+            None => default_language(&form.languages).with_context(|| {
+                format!(
+                    "Rust seems to be not available in problem {}...",
+                    problem.id
+                )
+            })?,
         };
 
+        println!(
+            "Submitting to problem `{}`, using language `{}` (ID: {})",
+            form.task_screen_name, language.name, language.id,
+        );
+
         let _ = self
             .http_post_form(
                 &format!("/contests/{}/submit", contest_id),
                 &[
-                    ("data.TaskScreenName", &task_screen_name),
-                    ("data.LanguageId", &language_id),
-                    ("sourceCode", &source_code),
-                    ("csrf_token", &csrf_token),
+                    ("data.TaskScreenName", &form.task_screen_name),
+                    ("data.LanguageId", &language.id),
+                    ("sourceCode", source_code),
+                    ("csrf_token", &form.csrf_token),
                 ],
             )
             .await?;
 
         println!(
             "Submitted to problem `{}`, using language `{}`",
-            task_screen_name, language_name
+            form.task_screen_name, language.name
         );
         Ok(())
     }
 
+    pub async fn standings(&self, contest_id: &str) -> Result<Standings> {
+        let json = self
+            .retrieve_text_or_error_message(
+                &format!("/contests/{}/standings/json", contest_id),
+                || format!("Could not find the standings of `{}`", contest_id),
+            )
+            .await?;
+        serde_json::from_str(&json).with_context(|| "Failed to parse the standings")
+    }
+
+    pub async fn clarifications(&self, contest_id: &str) -> Result<Vec<Clarification>> {
+        let doc = self
+            .retrieve_text_or_error_message(
+                &format!("/contests/{}/clarifications", contest_id),
+                || format!("Could not find clarifications of `{}`", contest_id),
+            )
+            .await?;
+        let doc = Html::parse_document(&doc);
+
+        // <tr>
+        //     <td><a href="/contests/abc152/tasks/abc152_a">A - AC or WA</a></td>
+        //     <td><a href="/users/tanakh">tanakh</a></td>
+        //     <td>question</td>
+        //     <td>answer</td>
+        //     <td>Yes</td>
+        //     <td><time class="fixtime-second">2020-01-19 21:10:00+0900</time></td>
+        // </tr>
+        let mut ret = vec![];
+        let (sel_td, sel_time) = (selector("td"), selector("time"));
+        for r in doc.select(&selector("table tbody tr")) {
+            let tds = r.select(&sel_td).collect_vec();
+            if tds.len() < 4 {
+                continue;
+            }
+            let text = |td: &ElementRef| td.text().collect::<String>().trim().to_owned();
+            let date = r
+                .select(&sel_time)
+                .next()
+                .and_then(|t| {
+                    DateTime::parse_from_str(
+                        t.text().collect::<String>().trim(),
+                        "%Y-%m-%d %H:%M:%S%z",
+                    )
+                    .ok()
+                })
+                .ok_or_else(|| Error::layout_changed("a clarification", &r.html()))?;
+
+            ret.push(Clarification {
+                task: text(&tds[0]),
+                question: text(&tds[2]),
+                answer: text(&tds[3]),
+                date: date.into(),
+            });
+        }
+        Ok(ret)
+    }
+
+    #[instrument(skip(self))]
     pub async fn submission_status(&self, contest_id: &str) -> Result<Vec<SubmissionResult>> {
         self.check_login().await?;
 
@@ -567,8 +1441,9 @@ impl AtCoder {
         let doc = Html::parse_document(&con);
 
         let mut ret = vec![];
+        let sel = selector("td");
 
-        for r in doc.select(&Selector::parse("table tbody tr").unwrap()) {
+        for r in doc.select(&selector("table tbody tr")) {
             // <td class="no-break"><time class="fixtime-second">2020-01-18 03:59:59</time></td>
             // <td><a href="/contests/abc123/tasks/abc123_a">A - Five Antennas</a></td>
             // <td><a href="/users/tanakh">tanakh</a> <a href="/contests/abc123/submissions?f.User=tanakh"><span class="glyphicon glyphicon-search black" aria-hidden="true" data-toggle="tooltip" title="" data-original-title="tanakhさんの提出を見る"></span></a></td>
@@ -581,7 +1456,6 @@ impl AtCoder {
             // <td class="text-center"><a href="/contests/abc123/submissions/9551881">詳細</a></td>
 
             let res = (|| -> Option<SubmissionResult> {
-                let sel = Selector::parse("td").unwrap();
                 let mut it = r.select(&sel);
 
                 let date = it.next()?.first_child()?.first_child()?.value().as_text()?;
@@ -637,16 +1511,13 @@ impl AtCoder {
                 })
             })();
 
-            if res.is_none() {
-                panic!("failed to parse result:\n{}", r.html());
-            }
-
-            ret.push(res.unwrap());
+            ret.push(res.ok_or_else(|| Error::layout_changed("a submission", &r.html()))?);
         }
 
         Ok(ret)
     }
 
+    #[instrument(skip(self))]
     pub async fn submission_status_full(
         &self,
         contest_id: &str,
@@ -699,9 +1570,8 @@ impl AtCoder {
         //     </tr>
         // </table>
 
+        let sel = selector("table tr th+td");
         let result = (|| -> Option<SubmissionResult> {
-            let sel = Selector::parse("table tr th+td").unwrap();
-
             let mut it = doc.select(&sel);
 
             let date = it.next()?.first_child()?.first_child()?.value().as_text()?;
@@ -742,7 +1612,7 @@ impl AtCoder {
                 memory: resource.map(|r| r.1),
             })
         })()
-        .with_context(|| "Failed to parse result")?;
+        .ok_or_else(|| Error::layout_changed("the submission", &doc.root_element().html()))?;
 
         // <table class="table table-bordered table-striped th-center">
         // <thead>
@@ -762,11 +1632,11 @@ impl AtCoder {
 
         // </tr>
 
-        let sel_td = Selector::parse("td").unwrap();
+        let sel_td = selector("td");
 
         let mut cases = vec![];
 
-        for r in doc.select(&Selector::parse("table tbody tr").unwrap()) {
+        for r in doc.select(&selector("table tbody tr")) {
             let case = (|| -> Option<CaseResult> {
                 let mut it = r.select(&sel_td);
                 let name = it.next()?.inner_html();
@@ -802,11 +1672,8 @@ impl AtCoder {
         match self.http_get(path).await {
             Err(err) if is_http_error(&err, reqwest::StatusCode::NOT_FOUND) => {
                 Err(match self.username().await {
-                    Ok(username) => err.context(if username.is_some() {
-                        anyhow!("{}", context_on_logged_in())
-                    } else {
-                        anyhow!("You are not logged in. Please login first")
-                    }),
+                    Ok(Some(_)) => err.context(context_on_logged_in().to_string()),
+                    Ok(None) => err.context(Error::NotLoggedIn),
                     Err(err) => err,
                 })?
             }
@@ -814,12 +1681,32 @@ impl AtCoder {
         }
     }
 
+    /// Gets a page that is visible without logging in, so 404 means that it does not exist.
+    async fn retrieve_public_text<T: fmt::Display, F: FnOnce() -> T>(
+        &self,
+        path: &str,
+        context_on_not_found: F,
+    ) -> Result<String> {
+        match self.http_get(path).await {
+            Err(err) if is_http_error(&err, reqwest::StatusCode::NOT_FOUND) => {
+                Err(err.context(context_on_not_found().to_string()))
+            }
+            ret => ret,
+        }
+    }
+
     async fn http_get(&self, path: &str) -> Result<String> {
         self.client
             .get(&format!("{}{}", ATCODER_ENDPOINT, path).parse::<Url>()?)
             .await
     }
 
+    async fn http_get_until_found(&self, path: &str) -> Result<String> {
+        self.client
+            .get_until_found(&format!("{}{}", ATCODER_ENDPOINT, path).parse::<Url>()?)
+            .await
+    }
+
     async fn http_post_form(&self, path: &str, form: &[(&str, &str)]) -> Result<String> {
         self.client
             .post_form(
@@ -829,3 +1716,147 @@ impl AtCoder {
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(html: &str) -> Vec<(String, String)> {
+        parse_test_cases(&Html::parse_document(html))
+            .unwrap()
+            .into_iter()
+            .map(|tc| (tc.input, tc.output))
+            .collect()
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|&(input, output)| (input.to_owned(), output.to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn sections_without_lang() {
+        let html = r#"<div id="task-statement">
+<div class="part"><section><h3>問題文</h3><p>...</p></section></div>
+<div class="io-style">
+<div class="part"><section><h3>入力</h3><pre><var>H_1</var>
+<var>H_2</var>
+</pre></section></div>
+<div class="part"><section><h3>入力例 1</h3><pre>15
+10
+</pre></section></div>
+<div class="part"><section><h3>出力例 1</h3><pre>5
+</pre></section></div>
+<div class="part"><section><h3>入力例 2</h3><pre>0
+0
+</pre></section></div>
+<div class="part"><section><h3>出力例 2</h3><pre>0
+</pre></section></div>
+</div>
+</div>"#;
+        assert_eq!(samples(html), pairs(&[("15\n10", "5"), ("0\n0", "0")]));
+    }
+
+    #[test]
+    fn sample_whitespace_as_in_doc_comments() {
+        let html = "<div id=\"task-statement\">
+<div class=\"part\"><section><h3>入力例 1</h3><pre> 1 2\r\n3\r\n\r\n</pre></section></div>
+<div class=\"part\"><section><h3>出力例 1</h3><pre>Yes\n\n</pre></section></div>
+</div>";
+        let scraped = samples(html);
+        assert_eq!(scraped, pairs(&[(" 1 2\n3", "Yes")]));
+
+        let source = "//! ## 入力例 1\n//!\n//! ```text\n//!  1 2\n//! 3\n//! ```\n//!\n//! ## 出力例 1\n//!\n//! ```text\n//! Yes\n//! ```\n";
+        let embedded = statement::read_doc_comment_samples(source)
+            .unwrap()
+            .into_iter()
+            .map(|t| (t.input, t.output))
+            .collect::<Vec<_>>();
+        assert_eq!(embedded, scraped);
+    }
+
+    #[test]
+    fn parts_without_h3() {
+        let html = r#"<div id="task-statement">
+<div class="part"><h3>入力</h3><pre><var>N</var>
+<var>c</var>
+</pre></div>
+<div class="part">
+<p><b>入力例1</b></p>
+<div class="section"><pre>9
+131142143
+</pre></div>
+<p><b>出力例1</b></p>
+<div class="section"><pre>4 1
+</pre></div>
+<p>1 が 4 個、2 が 1 個です。</p>
+</div>
+<div class="part">
+<p><b>入力例2</b></p>
+<div class="section"><pre>4
+1111
+</pre></div>
+<p><b>出力例2</b></p>
+<div class="section"><pre>4 0
+</pre></div>
+</div>
+</div>"#;
+        assert_eq!(
+            samples(html),
+            pairs(&[("9\n131142143", "4 1"), ("4\n1111", "4 0")]),
+        );
+    }
+
+    #[test]
+    fn pre_with_nested_var() {
+        let html = r#"<div id="task-statement">
+<div class="part"><section><h3>入力例 1</h3><pre><var>5</var> <var>7</var>
+</pre></section></div>
+<div class="part"><section><h3>出力例 1</h3><pre><span><var>YES</var></span>
+</pre></section></div>
+</div>"#;
+        assert_eq!(samples(html), pairs(&[("5 7", "YES")]));
+    }
+
+    #[test]
+    fn joi_mirror() {
+        let html = r#"<div id="task-statement">
+<h4>問題</h4>
+<p>おつりの硬貨の枚数を求めよ。</p>
+<h4>入力例</h4>
+入力例 1
+<pre><var>380</var>
+</pre>
+入力例 2
+<pre><var>1</var>
+</pre>
+<h4>出力例</h4>
+出力例 1
+<pre><var>4</var>
+</pre>
+出力例 2
+<pre><var>15</var>
+</pre>
+</div>"#;
+        assert_eq!(samples(html), pairs(&[("380", "4"), ("1", "15")]));
+    }
+
+    #[test]
+    fn rustc_version_in_language_names() {
+        let lang = |name: &str| Language {
+            id: "0".to_owned(),
+            name: name.to_owned(),
+        };
+        assert_eq!(rustc_version(&lang("Rust (rustc 1.70.0)")), Some("1.70.0"));
+        assert_eq!(rustc_version(&lang("Rust (1.42.0)")), Some("1.42.0"));
+        assert_eq!(rustc_version(&lang("Rust")), None);
+    }
+
+    #[test]
+    fn no_samples() {
+        let doc = Html::parse_document(r#"<div id="task-statement"><p>...</p></div>"#);
+        assert!(parse_test_cases(&doc).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use std::{collections::BTreeSet, fs, path::PathBuf};
+
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Local};
+use itertools::Itertools as _;
+
+use crate::{
+    atcoder::{AtCoder, Clarification},
+    cache_dir,
+};
+
+/// IDs of clarifications already announced, stored one per line.
+pub struct SeenClarifications {
+    path: PathBuf,
+    ids: BTreeSet<String>,
+}
+
+impl SeenClarifications {
+    pub fn load(contest_id: &str) -> Result<Self> {
+        let dir = cache_dir()?.join("clarifications");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(contest_id).with_extension("txt");
+
+        let ids = if path.exists() {
+            fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read `{}`", path.display()))?
+                .lines()
+                .map(ToOwned::to_owned)
+                .collect()
+        } else {
+            BTreeSet::new()
+        };
+
+        Ok(Self { path, ids })
+    }
+
+    fn save(&self) -> Result<()> {
+        let mut content = self.ids.iter().cloned().collect::<Vec<_>>().join("\n");
+        content.push('\n');
+        fs::write(&self.path, content)
+            .with_context(|| format!("Failed to write `{}`", self.path.display()))
+    }
+
+    /// Fetches clarifications and returns the answered ones not announced yet.
+    pub async fn take_new(
+        &mut self,
+        atc: &AtCoder,
+        contest_id: &str,
+    ) -> Result<Vec<Clarification>> {
+        let new = atc
+            .clarifications(contest_id)
+            .await?
+            .into_iter()
+            .filter(|c| c.answered() && !self.ids.contains(&c.id()))
+            .collect::<Vec<_>>();
+
+        if !new.is_empty() {
+            self.ids.extend(new.iter().map(Clarification::id));
+            self.save()?;
+        }
+        Ok(new)
+    }
+}
+
+pub fn format_clarification(c: &Clarification) -> Vec<String> {
+    vec![
+        format!(
+            "[Clarification] {} | {}",
+            DateTime::<Local>::from(c.date).format("%Y-%m-%d %H:%M:%S"),
+            if c.task.is_empty() { "(all)" } else { &c.task },
+        ),
+        format!("    Q: {}", c.question.split_whitespace().join(" ")),
+        format!("    A: {}", c.answer.split_whitespace().join(" ")),
+    ]
+}
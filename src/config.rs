@@ -1,13 +1,17 @@
 use anyhow::{Context as _, Result};
 use serde::Deserialize;
-use std::path::PathBuf;
-use std::{env, fs};
+use std::path::{Path, PathBuf};
+use std::{collections::BTreeSet, env, fs};
 use toml::Value;
 use toml_edit::Document;
 
+use crate::profile;
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct Config {
     pub atcoder: AtCoder,
+    #[serde(default)]
+    pub network: Network,
     pub profile: Profile,
     pub dependencies: Value,
     pub project: Project,
@@ -20,6 +24,45 @@ pub struct AtCoder {
     pub binary_column: usize,
     pub update_interval: u64,
     pub strip_path: Option<String>,
+    pub language: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Network {
+    /// Number of retries of failed GET requests
+    pub retries: u32,
+    /// Delay before the first retry, doubled on each retry (ms)
+    pub retry_delay: u64,
+    /// Minimum interval between requests (ms)
+    pub min_request_interval: u64,
+    /// HTTP or SOCKS5 proxy URL (e.g. `http://proxy:8080`, `socks5://localhost:1080`)
+    pub proxy: Option<String>,
+    /// Hosts not to access via the proxy
+    pub no_proxy: Vec<String>,
+    /// Additional root certificates (PEM or DER)
+    pub root_certificates: Vec<PathBuf>,
+    /// Connect timeout (s)
+    pub connect_timeout: Option<u64>,
+    /// Timeout of a whole request including reading the response (s)
+    pub timeout: Option<u64>,
+    pub user_agent: Option<String>,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            retry_delay: 1000,
+            min_request_interval: 200,
+            proxy: None,
+            no_proxy: vec![],
+            root_certificates: vec![],
+            connect_timeout: None,
+            timeout: None,
+            user_agent: None,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -32,6 +75,18 @@ pub struct Profile {
 pub struct Project {
     pub template: String,
     pub rustc_version: Option<String>,
+    /// Directory of templates, relative to the config file
+    pub template_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub template_overrides: Vec<TemplateOverride>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TemplateOverride {
+    /// `<contest_id>/<problem_id>`, where `*` matches any string
+    pub pattern: String,
+    /// Path in the template directory
+    pub template: PathBuf,
 }
 
 const DEFAULT_CONFIG_STR: &str = include_str!("../config/cargo-atcoder.toml");
@@ -52,7 +107,15 @@ fn config_path() -> Result<PathBuf> {
     Ok(config_path)
 }
 
-pub fn read_config() -> Result<Config> {
+/// Resolves `path` in the config relative to the directory of the config file.
+pub fn resolve_path(path: &Path) -> Result<PathBuf> {
+    let config_path = config_path()?;
+    Ok(config_path
+        .parent()
+        .map_or_else(|| path.to_owned(), |dir| dir.join(path)))
+}
+
+fn read_config_value() -> Result<Value> {
     let config_path = config_path()?;
     let s = fs::read_to_string(&config_path)
         .with_context(|| format!("Failed to read: `{}`", config_path.display()))?;
@@ -64,6 +127,49 @@ pub fn read_config() -> Result<Config> {
     })
 }
 
+/// Reads the config with the `[profiles.<name>]` table of the current profile merged in.
+pub fn read_config() -> Result<Config> {
+    let mut value = read_config_value()?;
+
+    let overrides = value
+        .as_table_mut()
+        .and_then(|t| t.remove("profiles"))
+        .and_then(|profiles| match (profile::current(), profiles) {
+            (Some(name), Value::Table(mut profiles)) => profiles.remove(name),
+            _ => None,
+        });
+    if let Some(overrides) = overrides {
+        merge(&mut value, overrides);
+    }
+
+    value
+        .try_into()
+        .with_context(|| "Failed to parse the config")
+}
+
+pub fn profile_names() -> Result<BTreeSet<String>> {
+    Ok(match read_config_value()?.get("profiles") {
+        Some(Value::Table(profiles)) => profiles.keys().cloned().collect(),
+        _ => BTreeSet::new(),
+    })
+}
+
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Table(base), Value::Table(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(base) => merge(base, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
 pub fn read_config_preserving() -> Result<Document> {
     let config_path = config_path()?;
     Ok(fs::read_to_string(&config_path)?.parse::<Document>()?)
@@ -0,0 +1,161 @@
+//! Errors that are reported with dedicated messages and exit codes.
+
+use std::fmt;
+
+use chrono::{DateTime, Local, Utc};
+
+#[derive(Debug)]
+pub enum Error {
+    NotLoggedIn,
+    NotRegistered {
+        contest_id: String,
+    },
+    ContestNotStarted {
+        contest_id: String,
+        start: DateTime<Utc>,
+    },
+    ProblemNotFound {
+        contest_id: String,
+        problem_id: String,
+    },
+    /// A page could not be parsed, probably because AtCoder changed its layout.
+    LayoutChanged {
+        what: &'static str,
+        snippet: String,
+    },
+    RateLimited,
+    Unavailable,
+}
+
+impl Error {
+    pub fn layout_changed(what: &'static str, html: &str) -> Self {
+        const MAX_SNIPPET_LEN: usize = 1000;
+
+        let html = html.trim();
+        let snippet = match html.char_indices().nth(MAX_SNIPPET_LEN) {
+            Some((i, _)) => format!("{}...", &html[..i]),
+            None => html.to_owned(),
+        };
+        Error::LayoutChanged { what, snippet }
+    }
+
+    /// 1 is for other errors, including invalid arguments (clap exits with 1 for them).
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::NotLoggedIn => 3,
+            Error::NotRegistered { .. } => 4,
+            Error::ContestNotStarted { .. } => 5,
+            Error::ProblemNotFound { .. } => 6,
+            Error::LayoutChanged { .. } => 7,
+            Error::RateLimited => 8,
+            Error::Unavailable => 9,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotLoggedIn => write!(
+                f,
+                "You are not logged in, or the session has expired. Run `cargo atcoder login` first"
+            ),
+            Error::NotRegistered { contest_id } => write!(
+                f,
+                "You are not registered for `{}`. Run `cargo atcoder register` first",
+                contest_id
+            ),
+            Error::ContestNotStarted { contest_id, start } => write!(
+                f,
+                "`{}` has not started yet. It starts at {} (`cargo atcoder new --wait` waits for it)",
+                contest_id,
+                DateTime::<Local>::from(*start).format("%Y-%m-%d %H:%M:%S"),
+            ),
+            Error::ProblemNotFound {
+                contest_id,
+                problem_id,
+            } => write!(
+                f,
+                "Problem `{}` is not contained in `{}`",
+                problem_id, contest_id
+            ),
+            Error::LayoutChanged { what, snippet } => write!(
+                f,
+                "Failed to parse {}. The page layout of AtCoder may have changed; please report \
+                 this with the pages saved by `cargo atcoder -vv --dump-responses <DIR> ...`\n{}",
+                what, snippet
+            ),
+            Error::RateLimited => write!(
+                f,
+                "AtCoder is rate limiting requests. Please wait a while and try again"
+            ),
+            Error::Unavailable => write!(
+                f,
+                "AtCoder is temporarily unavailable (possibly under maintenance). Please try again later"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The exit code for `err`, which is 1 unless it is caused by an [`Error`].
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    err.downcast_ref::<Error>().map_or(1, Error::exit_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn exit_codes_are_distinct() {
+        let start = Utc::now();
+        let errors = [
+            Error::NotLoggedIn,
+            Error::NotRegistered {
+                contest_id: "abc001".to_owned(),
+            },
+            Error::ContestNotStarted {
+                contest_id: "abc001".to_owned(),
+                start,
+            },
+            Error::ProblemNotFound {
+                contest_id: "abc001".to_owned(),
+                problem_id: "z".to_owned(),
+            },
+            Error::layout_changed("the task list", "<html></html>"),
+            Error::RateLimited,
+            Error::Unavailable,
+        ];
+        let codes = errors.iter().map(Error::exit_code).collect::<Vec<_>>();
+        assert_eq!(codes, [3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn exit_code_through_context() {
+        assert_eq!(exit_code(&anyhow!("other")), 1);
+        assert_eq!(exit_code(&anyhow!(Error::RateLimited)), 8);
+        assert_eq!(
+            exit_code(&anyhow!("404 Not Found").context(Error::NotLoggedIn)),
+            3,
+        );
+        assert_eq!(
+            exit_code(&anyhow!(Error::Unavailable).context("while fetching the task list")),
+            9,
+        );
+    }
+
+    #[test]
+    fn layout_changed_snippet_is_truncated() {
+        let html = "あ".repeat(1500);
+        match Error::layout_changed("the task list", &format!("  {}  ", html)) {
+            Error::LayoutChanged { what, snippet } => {
+                assert_eq!(what, "the task list");
+                assert_eq!(snippet, format!("{}...", "あ".repeat(1000)));
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+}
@@ -1,92 +1,530 @@
-use anyhow::{anyhow, Result};
-use itertools::Itertools;
+use anyhow::{anyhow, Context as _, Result};
+use fs2::FileExt;
+use once_cell::sync::{Lazy, OnceCell};
 use reqwest::{
     cookie::{CookieStore, Jar},
-    header::HeaderValue,
-    Client as ReqwestClient, Url,
+    header::{HeaderValue, RETRY_AFTER},
+    redirect, Certificate, Client as ReqwestClient, Proxy, RequestBuilder, Response, StatusCode,
+    Url,
 };
 use std::{
-    fs::File,
-    io::{BufRead, BufReader, Write as _},
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Write as _},
     path::{Path, PathBuf},
-    sync::Arc,
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+
+use crate::{config::Network, error::Error};
 
 pub struct Client {
     client: ReqwestClient,
+    config: Network,
     cookie_store: Arc<Jar>,
-    session_file: PathBuf,
+    /// `None` for a client whose cookies are never saved.
+    session_file: Option<PathBuf>,
     endpoint: String,
+    /// Cookies as last read from or written to `session_file`.
+    saved_cookies: Mutex<Cookies>,
 }
 
-impl Drop for Client {
-    fn drop(&mut self) {
-        let result = (|| -> anyhow::Result<()> {
-            let mut file = File::create(&self.session_file)
-                .map_err(|e| anyhow!("failed to open `{}`: {}", self.session_file.display(), e))?;
+fn lock_path(session_file: &Path) -> PathBuf {
+    let mut path = session_file.as_os_str().to_owned();
+    path.push(".lock");
+    PathBuf::from(path)
+}
 
-            for cookie in self
-                .cookie_store
-                .cookies(&self.endpoint.parse::<Url>().unwrap())
-            {
-                writeln!(&mut file, "{}", cookie.to_str()?)?;
-            }
+/// The lock file guarding `session_file` across processes.
+fn lock_file(session_file: &Path) -> Result<File> {
+    let path = lock_path(session_file);
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .with_context(|| format!("failed to open `{}`", path.display()))
+}
 
-            Ok(())
-        })();
+/// Cookies by name.
+type Cookies = BTreeMap<String, String>;
 
-        if let Err(err) = result {
-            let _ = eprintln!("An error occurred while saving the session: {}", err);
+/// Parses cookies in the form of a `Cookie` header (`name1=value1; name2=value2`).
+fn parse_cookies(header: &str) -> Cookies {
+    header
+        .split("; ")
+        .filter_map(|cookie| cookie.split_once('='))
+        .map(|(name, value)| (name.to_owned(), value.to_owned()))
+        .collect()
+}
+
+fn format_cookies(cookies: &Cookies) -> String {
+    cookies
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Applies the changes from `base` to `ours` onto `theirs`, so that cookies saved by other
+/// processes in the meantime are kept unless this process has changed or removed them.
+fn merge_cookies(base: &Cookies, ours: &Cookies, mut theirs: Cookies) -> Cookies {
+    for name in base.keys().chain(ours.keys()) {
+        if base.get(name) != ours.get(name) {
+            match ours.get(name) {
+                Some(value) => theirs.insert(name.clone(), value.clone()),
+                None => theirs.remove(name),
+            };
+        }
+    }
+    theirs
+}
+
+/// Reads `session_file`, or returns `None` if it does not exist.
+fn read_session_file(session_file: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(session_file) {
+        Ok(content) => Ok(Some(content)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => {
+            Err(anyhow!(err).context(format!("failed to read `{}`", session_file.display())))
         }
     }
 }
 
-fn load_cookie_store(session_file: &Path, endpoint: &str) -> Result<Jar> {
+fn load_cookie_store(session_file: &Path, endpoint: &str) -> Result<(Jar, Cookies)> {
     let url = endpoint.parse().unwrap();
     let jar = reqwest::cookie::Jar::default();
-    let f = File::open(session_file);
 
-    if f.is_err() {
-        return Ok(jar);
-    }
+    let lock = lock_file(session_file)?;
+    FileExt::lock_shared(&lock)?;
+    let content = read_session_file(session_file);
+    FileExt::unlock(&lock)?;
 
-    for line in BufReader::new(f?).lines() {
-        let v = line?
+    let content = match content? {
+        Some(content) => content,
+        None => return Ok((jar, Cookies::new())),
+    };
+
+    for line in content.lines() {
+        let v = line
             .split("; ")
-            .map(|s| HeaderValue::from_str(s).unwrap())
-            .collect_vec();
+            .map(HeaderValue::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("broken session file: `{}`", session_file.display()))?;
         jar.set_cookies(&mut v.iter(), &url)
     }
-    Ok(jar)
+    Ok((
+        jar,
+        content
+            .lines()
+            .next()
+            .map(parse_cookies)
+            .unwrap_or_default(),
+    ))
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Removes `session_file` together with its lock file.
+pub fn remove_session(session_file: &Path) -> Result<()> {
+    let lock = lock_file(session_file)?;
+    FileExt::lock_exclusive(&lock)?;
+    let result = remove_if_exists(session_file)
+        .and_then(|()| remove_if_exists(&lock_path(session_file)))
+        .with_context(|| format!("failed to remove `{}`", session_file.display()));
+    FileExt::unlock(&lock)?;
+    result
+}
+
+/// Writes `content` to a temporary file readable only by the user and renames it to `path`.
+fn write_atomically(path: &Path, content: &str) -> Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(format!(".{}.tmp", process::id()));
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let mut options = OpenOptions::new();
+    options.create(true).write(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let result = (|| -> Result<()> {
+        let mut file = options.open(&tmp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
 }
 
 impl Client {
-    pub fn new(session_file: &Path, endpoint: &str) -> Result<Self> {
+    pub fn new(session_file: &Path, endpoint: &str, config: &Network) -> Result<Self> {
+        let (cookie_store, saved_cookies) = load_cookie_store(session_file, endpoint)?;
+        Self::with_cookie_store(
+            cookie_store,
+            saved_cookies,
+            Some(session_file.to_owned()),
+            endpoint,
+            config,
+        )
+    }
+
+    pub fn in_memory(endpoint: &str, config: &Network) -> Result<Self> {
+        Self::with_cookie_store(Jar::default(), Cookies::new(), None, endpoint, config)
+    }
+
+    fn with_cookie_store(
+        cookie_store: Jar,
+        saved_cookies: Cookies,
+        session_file: Option<PathBuf>,
+        endpoint: &str,
+        config: &Network,
+    ) -> Result<Self> {
         static USER_AGENT: &str = "cargo-atcoder";
 
-        let cookie_store = Arc::new(load_cookie_store(session_file, endpoint)?);
+        let cookie_store = Arc::new(cookie_store);
 
-        let client = reqwest::ClientBuilder::new()
+        let mut builder = reqwest::ClientBuilder::new()
+            .redirect(redirect::Policy::custom(|attempt| {
+                debug!(from = %attempt.previous().last().unwrap(), to = %attempt.url(), status = %attempt.status(), "redirect");
+                if attempt.previous().len() >= 10 {
+                    attempt.error("too many redirects")
+                } else {
+                    attempt.follow()
+                }
+            }))
             .cookie_provider(cookie_store.clone())
-            .user_agent(USER_AGENT)
-            .build()?;
+            .user_agent(config.user_agent.as_deref().unwrap_or(USER_AGENT));
+
+        if let Some(proxy) = &config.proxy {
+            builder = builder.proxy(proxy_with_exceptions(proxy, &config.no_proxy)?);
+        }
+        for path in &config.root_certificates {
+            builder = builder.add_root_certificate(load_certificate(path)?);
+        }
+        if let Some(secs) = config.connect_timeout {
+            builder = builder.connect_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = config.timeout {
+            builder = builder.timeout(Duration::from_secs(secs));
+        }
+
+        let client = builder.build()?;
 
         Ok(Self {
             client,
+            config: config.clone(),
             cookie_store,
-            session_file: session_file.to_owned(),
+            session_file,
             endpoint: endpoint.to_owned(),
+            saved_cookies: Mutex::new(saved_cookies),
         })
     }
 
+    /// Saves the cookies if they have changed since they were last loaded or saved, merging
+    /// them with the cookies saved by other processes in the meantime. The session file is
+    /// removed if no cookies remain.
+    pub fn save_session(&self) -> Result<()> {
+        let session_file = match &self.session_file {
+            Some(session_file) => session_file,
+            None => return Ok(()),
+        };
+        let url = self.endpoint.parse::<Url>()?;
+        let cookies = match self.cookie_store.cookies(&url) {
+            Some(cookies) => parse_cookies(cookies.to_str()?),
+            None => Cookies::new(),
+        };
+
+        let mut saved_cookies = self.saved_cookies.lock().unwrap();
+        if *saved_cookies == cookies {
+            return Ok(());
+        }
+
+        let merged = (|| -> Result<Cookies> {
+            let lock = lock_file(session_file)?;
+            FileExt::lock_exclusive(&lock)?;
+            let result = (|| -> Result<Cookies> {
+                let theirs = read_session_file(session_file)?
+                    .and_then(|content| content.lines().next().map(parse_cookies))
+                    .unwrap_or_default();
+                let merged = merge_cookies(&saved_cookies, &cookies, theirs);
+                if merged.is_empty() {
+                    remove_if_exists(session_file)?;
+                } else {
+                    write_atomically(session_file, &format!("{}\n", format_cookies(&merged)))?;
+                }
+                Ok(merged)
+            })();
+            FileExt::unlock(&lock)?;
+            result
+        })()
+        .with_context(|| {
+            format!(
+                "An error occurred while saving the session to `{}`",
+                session_file.display()
+            )
+        })?;
+
+        // Take in the cookies of the other processes, so that the next save does not regard
+        // them as removed by this process.
+        let mut updates = vec![];
+        for (name, value) in &merged {
+            if cookies.get(name) != Some(value) {
+                updates.push(HeaderValue::from_str(&format!("{}={}", name, value))?);
+            }
+        }
+        for name in cookies.keys().filter(|name| !merged.contains_key(*name)) {
+            updates.push(HeaderValue::from_str(&format!("{}=; Max-Age=0", name))?);
+        }
+        self.cookie_store.set_cookies(&mut updates.iter(), &url);
+
+        *saved_cookies = merged;
+        Ok(())
+    }
+
+    pub fn set_cookie(&self, cookie: &str) -> Result<()> {
+        let url = self.endpoint.parse::<Url>()?;
+        let value = HeaderValue::from_str(cookie)?;
+        self.cookie_store
+            .set_cookies(&mut std::iter::once(&value), &url);
+        Ok(())
+    }
+
     pub async fn get(&self, url: &Url) -> Result<String> {
-        let resp = self.client.get(url.clone()).send();
-        Ok(resp.await?.error_for_status()?.text().await?)
+        self.get_with(url, Retry::Transient).await
+    }
+
+    /// Like [`get`](Self::get), but also retries while the page is not found, for pages that
+    /// appear shortly (e.g. the task list right after a contest starts).
+    pub async fn get_until_found(&self, url: &Url) -> Result<String> {
+        self.get_with(url, Retry::UntilFound).await
     }
 
+    async fn get_with(&self, url: &Url, retry: Retry) -> Result<String> {
+        let resp = self
+            .send("GET", url, || self.client.get(url.clone()), retry)
+            .await?;
+        let body = resp.text().await?;
+        dump_response("GET", url, &body);
+        Ok(body)
+    }
+
+    /// Sends a form. This is never retried since it may not be idempotent (e.g. submissions).
     pub async fn post_form(&self, url: &Url, form: &[(&str, &str)]) -> Result<String> {
-        let resp = self.client.post(url.clone()).form(form).send();
-        Ok(resp.await?.error_for_status()?.text().await?)
+        let resp = self
+            .send(
+                "POST",
+                url,
+                || self.client.post(url.clone()).form(form),
+                Retry::Never,
+            )
+            .await?;
+        let body = resp.text().await?;
+        dump_response("POST", url, &body);
+        Ok(body)
+    }
+
+    /// Sends a request, retrying with exponential backoff on the failures in `retry`.
+    async fn send(
+        &self,
+        method: &str,
+        url: &Url,
+        build: impl Fn() -> RequestBuilder,
+        retry: Retry,
+    ) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            self.throttle().await;
+            let start = Instant::now();
+            let result = build().send().await;
+            let elapsed = start.elapsed().as_millis();
+            match &result {
+                Ok(resp) => {
+                    info!(%method, %url, status = %resp.status(), elapsed_ms = elapsed as u64);
+                    if resp.url() != url {
+                        debug!(%method, %url, final_url = %resp.url(), "redirected");
+                    }
+                    self.save_session()?;
+                }
+                Err(err) => info!(%method, %url, elapsed_ms = elapsed as u64, error = %err),
+            }
+
+            let retry_after = match &result {
+                Ok(resp) if is_retryable_status(resp.status()) => Some(retry_after(resp)),
+                Ok(resp)
+                    if retry == Retry::UntilFound && resp.status() == StatusCode::NOT_FOUND =>
+                {
+                    Some(None)
+                }
+                Err(err) if err.is_timeout() || err.is_connect() => Some(None),
+                _ => None,
+            };
+
+            match retry_after {
+                Some(retry_after) if retry != Retry::Never && attempt < self.config.retries => {
+                    let delay = retry_after.unwrap_or_else(|| {
+                        Duration::from_millis(self.config.retry_delay) * 2u32.pow(attempt)
+                    });
+                    let reason = match &result {
+                        Ok(resp) => resp.status().to_string(),
+                        Err(err) => err.to_string(),
+                    };
+                    eprintln!(
+                        "Request to {} failed ({}). Retrying in {:.1}s... ({}/{})",
+                        url,
+                        reason,
+                        delay.as_secs_f64(),
+                        attempt + 1,
+                        self.config.retries,
+                    );
+                    sleep(delay).await;
+                    attempt += 1;
+                }
+                _ => return check_status(result?),
+            }
+        }
+    }
+
+    /// Waits so that requests from this process are at least `min_request_interval` apart.
+    async fn throttle(&self) {
+        static NEXT_REQUEST: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+        let interval = Duration::from_millis(self.config.min_request_interval);
+        let now = Instant::now();
+        let start = {
+            let mut next = NEXT_REQUEST.lock().unwrap();
+            let start = next.map_or(now, |next| next.max(now));
+            *next = Some(start + interval);
+            start
+        };
+        sleep(start - now).await;
+    }
+}
+
+/// The failures a request is retried on.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Retry {
+    Never,
+    /// Timeouts, connection errors and 5xx/429 responses.
+    Transient,
+    /// Also 404 responses.
+    UntilFound,
+}
+
+static DUMP_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// Makes every response body be written into `dir`, for attaching to bug reports.
+pub fn set_dump_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create `{}`", dir.display()))?;
+    let _ = DUMP_DIR.set(dir.to_owned());
+    Ok(())
+}
+
+fn dump_response(method: &str, url: &Url, body: &str) {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = match DUMP_DIR.get() {
+        Some(dir) => dir,
+        None => return,
+    };
+    let name = format!(
+        "{}{}",
+        url.path(),
+        url.query().map_or(String::new(), |q| format!("?{}", q))
+    )
+    .chars()
+    .map(|c| {
+        if c.is_ascii_alphanumeric() || c == '-' {
+            c
+        } else {
+            '_'
+        }
+    })
+    .take(100)
+    .collect::<String>();
+    let path = dir.join(format!(
+        "{:04}-{}{}.html",
+        COUNT.fetch_add(1, Ordering::Relaxed),
+        method,
+        name,
+    ));
+
+    match fs::write(&path, body) {
+        Ok(()) => debug!(%url, path = %path.display(), "dumped the response"),
+        Err(err) => warn!(path = %path.display(), error = %err, "failed to dump the response"),
+    }
+}
+
+fn proxy_with_exceptions(proxy: &str, no_proxy: &[String]) -> Result<Proxy> {
+    let proxy_url = proxy
+        .parse::<Url>()
+        .with_context(|| format!("Invalid proxy URL: `{}`", proxy))?;
+    // Validate the scheme here since `Proxy::custom` cannot report errors.
+    Proxy::all(proxy_url.clone()).with_context(|| format!("Invalid proxy URL: `{}`", proxy))?;
+
+    let no_proxy = no_proxy.to_owned();
+    Ok(Proxy::custom(move |url| {
+        let host = url.host_str()?;
+        let excluded = no_proxy.iter().any(|pattern| {
+            let domain = pattern.trim_start_matches('.');
+            pattern == "*"
+                || host.eq_ignore_ascii_case(domain)
+                || host
+                    .to_lowercase()
+                    .ends_with(&format!(".{}", domain.to_lowercase()))
+        });
+        if excluded {
+            None
+        } else {
+            Some(proxy_url.clone())
+        }
+    }))
+}
+
+fn load_certificate(path: &Path) -> Result<Certificate> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read `{}`", path.display()))?;
+    Certificate::from_pem(&bytes)
+        .or_else(|_| Certificate::from_der(&bytes))
+        .with_context(|| format!("Invalid certificate: `{}`", path.display()))
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// The `Retry-After` header in seconds, capped to a minute.
+fn retry_after(resp: &Response) -> Option<Duration> {
+    let secs = resp
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .parse::<u64>()
+        .ok()?;
+    Some(Duration::from_secs(secs.min(60)))
+}
+
+fn check_status(resp: Response) -> Result<Response> {
+    let status = resp.status();
+    let result = resp.error_for_status();
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => Ok(result.context(Error::RateLimited)?),
+        StatusCode::SERVICE_UNAVAILABLE => Ok(result.context(Error::Unavailable)?),
+        _ => Ok(result?),
     }
 }
 
@@ -96,3 +534,73 @@ pub fn is_http_error(err: &anyhow::Error, status_code: reqwest::StatusCode) -> b
         Some(err) if err.status() == Some(status_code),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cookies(header: &str) -> Cookies {
+        parse_cookies(header)
+    }
+
+    #[test]
+    fn merge_keeps_cookies_saved_by_others() {
+        let base = cookies("a=1; b=1");
+        let ours = cookies("a=2; b=1");
+        let theirs = cookies("a=1; b=3; c=3");
+        assert_eq!(
+            merge_cookies(&base, &ours, theirs),
+            cookies("a=2; b=3; c=3")
+        );
+    }
+
+    #[test]
+    fn merge_removes_cookies_removed_by_us() {
+        let base = cookies("a=1; b=1");
+        let ours = cookies("b=1");
+        assert_eq!(
+            merge_cookies(&base, &ours, cookies("a=1; b=2")),
+            cookies("b=2")
+        );
+        assert!(merge_cookies(&base, &Cookies::new(), cookies("a=1; b=1")).is_empty());
+    }
+
+    #[test]
+    fn save_session_merges_and_removes_the_file() -> Result<()> {
+        let dir = tempdir::TempDir::new("cargo-atcoder-test-session")?;
+        let session_file = dir.path().join("session");
+        let endpoint = "https://atcoder.jp/";
+        fs::write(&session_file, "a=1\n")?;
+
+        let client = Client::new(&session_file, endpoint, &Network::default())?;
+        fs::write(&session_file, "a=1; b=2\n")?;
+        client.set_cookie("c=3")?;
+        client.save_session()?;
+        assert_eq!(fs::read_to_string(&session_file)?, "a=1; b=2; c=3\n");
+
+        client.set_cookie("a=; Max-Age=0")?;
+        client.set_cookie("b=; Max-Age=0")?;
+        client.set_cookie("c=; Max-Age=0")?;
+        client.save_session()?;
+        assert!(!session_file.exists());
+
+        dir.close().map_err(Into::into)
+    }
+    #[test]
+    fn remove_session_removes_the_lock_file() -> Result<()> {
+        let dir = tempdir::TempDir::new("cargo-atcoder-test-remove-session")?;
+        let session_file = dir.path().join("session");
+
+        let client = Client::new(&session_file, "https://atcoder.jp/", &Network::default())?;
+        client.set_cookie("a=1")?;
+        client.save_session()?;
+        assert!(lock_path(&session_file).exists());
+
+        remove_session(&session_file)?;
+        assert!(!session_file.exists());
+        assert!(!lock_path(&session_file).exists());
+        remove_session(&session_file)?;
+
+        dir.close().map_err(Into::into)
+    }
+}
@@ -2,20 +2,20 @@ use std::{
     cmp::max,
     collections::BTreeMap,
     env, fs,
-    io::Write,
+    io::{self, Write},
     path::{Path, PathBuf},
-    process::{Command, Stdio},
+    process::{self, Command, Stdio},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use anyhow::{bail, ensure, Context as _, Result};
+use anyhow::{anyhow, bail, ensure, Context as _, Result};
 use bytesize::ByteSize;
 use cargo_metadata::{Metadata, Package, Target};
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, Utc};
 use console::Style;
 use futures::join;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
@@ -29,17 +29,31 @@ use unicode_width::UnicodeWidthStr as _;
 use crate::metadata::{MetadataExt as _, PackageExt as _};
 
 mod atcoder;
+mod clarification;
 mod config;
+mod error;
 mod http;
 mod metadata;
+mod profile;
+mod rating;
+mod standings;
+mod statement;
+mod template;
+mod user;
 
 #[cfg(feature = "watch")]
 mod watch;
 
 use atcoder::*;
+use clarification::{format_clarification, SeenClarifications};
 use config::{read_config, read_config_preserving, Config};
+use template::Templates;
 
 fn session_file() -> Result<PathBuf> {
+    profile::session_file_for(profile::current())
+}
+
+fn cache_dir() -> Result<PathBuf> {
     let dir = if let Some(dir) = env::var_os("CARGO_ATCODER_TEST_CACHE_DIR") {
         dir.into()
     } else {
@@ -55,7 +69,7 @@ fn session_file() -> Result<PathBuf> {
         fs::create_dir_all(&dir)?;
     }
 
-    Ok(dir.join("session.txt"))
+    Ok(dir)
 }
 
 #[derive(StructOpt)]
@@ -64,9 +78,21 @@ struct NewOpt {
     contest_id: String,
 
     /// Create src/bin/<NAME>.rs without retrieving actual problem IDs
-    #[structopt(short, long, value_name("NAME"))]
+    #[structopt(short, long, value_name("NAME"), conflicts_with = "wait")]
     bins: Vec<String>,
 
+    /// Wait for the contest to start, then fetch the problems
+    #[structopt(long)]
+    wait: bool,
+
+    /// Prepend the statement and samples to each bin as a doc comment
+    #[structopt(long)]
+    doc_comment: bool,
+
+    /// Language of the doc comment (ja or en)
+    #[structopt(long, default_value = "ja")]
+    lang: statement::Lang,
+
     /// Skip warming-up after creating project.
     #[structopt(long)]
     skip_warmup: bool,
@@ -74,50 +100,575 @@ struct NewOpt {
 
 async fn new_project(opt: NewOpt) -> Result<()> {
     let config = read_config()?;
+    let atc = AtCoder::new(&session_file()?)?;
+
+    // `--bins` works offline, so the contest may not exist.
+    let on_atcoder = opt.bins.is_empty();
+    let (bins, contest_info) = problem_bins(&atc, &opt.contest_id, opt.bins, opt.wait).await?;
+
+    let templates = Templates::new(&config.project, None)?;
+    let files = package_files(
+        &atc,
+        &templates,
+        &opt.contest_id,
+        &bins,
+        contest_info.as_ref(),
+        if opt.doc_comment {
+            Some(opt.lang)
+        } else {
+            None
+        },
+    )
+    .await?;
+    let dir = Path::new(&opt.contest_id);
+    create_package(&config, dir, &files, false)?;
+
+    println!("Creating project done.");
+
+    if on_atcoder {
+        offer_registration(&atc, &opt.contest_id).await;
+    }
+
+    if !opt.skip_warmup {
+        let metadata = metadata::cargo_metadata(None, format!("./{}", opt.contest_id).as_ref())?;
+        warmup_for(&metadata, Some(&[&opt.contest_id]))?;
+        println!("Warming up done.");
+    }
+
+    Ok(())
+}
+
+#[derive(StructOpt)]
+struct AddOpt {
+    /// Contest ID (e.g. abc123)
+    contest_id: String,
+
+    /// Create src/bin/<NAME>.rs without retrieving actual problem IDs
+    #[structopt(short, long, value_name("NAME"), conflicts_with = "wait")]
+    bins: Vec<String>,
+
+    /// Wait for the contest to start, then fetch the problems
+    #[structopt(long)]
+    wait: bool,
+
+    /// Prepend the statement and samples to each bin as a doc comment
+    #[structopt(long)]
+    doc_comment: bool,
+
+    /// Language of the doc comment (ja or en)
+    #[structopt(long, default_value = "ja")]
+    lang: statement::Lang,
+
+    /// Skip warming-up after creating the member.
+    #[structopt(long)]
+    skip_warmup: bool,
+
+    /// [cargo] Path to Cargo.toml of the workspace root
+    #[structopt(long, value_name("PATH"))]
+    manifest_path: Option<PathBuf>,
+}
+
+async fn add_member(opt: AddOpt) -> Result<()> {
+    let config = read_config()?;
+    let atc = AtCoder::new(&session_file()?)?;
 
-    let bins = if !opt.bins.is_empty() {
-        opt.bins
+    let root_manifest_path = match opt.manifest_path {
+        Some(path) => path
+            .canonicalize()
+            .with_context(|| format!("Failed to read {}", path.display()))?,
+        None => find_workspace_root(&env::current_dir().with_context(|| "failed to get CWD")?)?,
+    };
+    let root_manifest = fs::read_to_string(&root_manifest_path)
+        .with_context(|| format!("Failed to read {}", root_manifest_path.display()))?
+        .parse::<toml_edit::Document>()?;
+    if !root_manifest.as_table().contains_table("workspace") {
+        bail!("{} has no `[workspace]`", root_manifest_path.display());
+    }
+
+    // `--bins` works offline, so the contest may not exist.
+    let on_atcoder = opt.bins.is_empty();
+    let (bins, contest_info) = problem_bins(&atc, &opt.contest_id, opt.bins, opt.wait).await?;
+
+    let root_dir = root_manifest_path
+        .parent()
+        .with_context(|| "the manifest path should have a parent")?;
+    // Created by `cargo atcoder init`.
+    let templates_dir = root_dir.join("templates");
+    let templates = Templates::new(
+        &config.project,
+        Some(templates_dir).filter(|dir| dir.is_dir()),
+    )?;
+    let files = package_files(
+        &atc,
+        &templates,
+        &opt.contest_id,
+        &bins,
+        contest_info.as_ref(),
+        if opt.doc_comment {
+            Some(opt.lang)
+        } else {
+            None
+        },
+    )
+    .await?;
+    create_package(&config, &root_dir.join(&opt.contest_id), &files, true)?;
+
+    // Newer `cargo new` adds the member by itself.
+    let mut root_manifest =
+        fs::read_to_string(&root_manifest_path)?.parse::<toml_edit::Document>()?;
+    let mut modified = false;
+    let members = &mut root_manifest["workspace"]["members"];
+    if members.is_none() {
+        *members = toml_edit::value(toml_edit::Array::default());
+    }
+    let contest_id = opt.contest_id.as_str();
+    let members = members
+        .as_value_mut()
+        .and_then(toml_edit::Value::as_array_mut)
+        .with_context(|| "`workspace.members` should be an array")?;
+    if !members.iter().any(|m| m.as_str() == Some(contest_id)) {
+        members
+            .push(contest_id)
+            .map_err(|_| anyhow!("`workspace.members` should be an array of strings"))?;
+        members.fmt();
+        modified = true;
+    }
+    // Profiles are only read from the workspace root.
+    if !root_manifest.as_table().contains_table("profile") {
+        root_manifest["profile"] = implicit_table();
+    }
+    if root_manifest["profile"]["release"].is_none() {
+        root_manifest["profile"]["release"] =
+            read_config_preserving()?["profile"]["release"].clone();
+        modified = true;
+    }
+    if modified {
+        fs::write(&root_manifest_path, root_manifest.to_string())?;
+    }
+
+    println!("Added `{}` to the workspace.", opt.contest_id);
+
+    if on_atcoder {
+        offer_registration(&atc, &opt.contest_id).await;
+    }
+
+    if !opt.skip_warmup {
+        let metadata = metadata::cargo_metadata(Some(&root_manifest_path), root_dir)?;
+        warmup_for(&metadata, Some(&[&opt.contest_id]))?;
+        println!("Warming up done.");
+    }
+
+    Ok(())
+}
+
+/// The nearest `Cargo.toml` with `[workspace]` in `dir` or its ancestors.
+fn find_workspace_root(dir: &Path) -> Result<PathBuf> {
+    for dir in dir.ancestors() {
+        let path = dir.join("Cargo.toml");
+        if let Ok(manifest) = fs::read_to_string(&path) {
+            let manifest = manifest.parse::<toml_edit::Document>()?;
+            if manifest.as_table().contains_table("workspace") {
+                return Ok(path);
+            }
+        }
+    }
+    bail!(
+        "could not find a workspace root `Cargo.toml` in `{}` or any parent directory",
+        dir.display()
+    )
+}
+
+#[derive(StructOpt)]
+struct InitOpt {
+    /// Directory to set up as the contest root
+    #[structopt(default_value = ".")]
+    dir: PathBuf,
+
+    /// Rust version of the judge, written to `rust-toolchain` (overwrite config)
+    #[structopt(long, value_name("VERSION"))]
+    rustc_version: Option<String>,
+
+    /// Skip pre-building the dependencies
+    #[structopt(long)]
+    skip_warmup: bool,
+}
+
+/// A temporary member to pre-build the dependencies in the config.
+const PREBUILD_MEMBER: &str = ".prebuild";
+
+async fn init_workspace(opt: InitOpt) -> Result<()> {
+    let config = read_config()?;
+    let conf_preserved = read_config_preserving()?;
+    let dir = &opt.dir;
+
+    let manifest_path = dir.join("Cargo.toml");
+    if manifest_path.exists() {
+        bail!("{} already exists", manifest_path.display());
+    }
+    fs::create_dir_all(dir)?;
+
+    // Also shared with projects created by `new` under this directory.
+    let cargo_config_path = dir.join(".cargo").join("config.toml");
+    let mut cargo_config = match fs::read_to_string(&cargo_config_path) {
+        Ok(s) => s.parse::<toml_edit::Document>()?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => toml_edit::Document::new(),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("Failed to read {}", cargo_config_path.display()))
+        }
+    };
+    if !cargo_config.as_table().contains_table("build") {
+        cargo_config["build"] = toml_edit::table();
+    }
+    if cargo_config["build"]["target-dir"].is_none() {
+        cargo_config["build"]["target-dir"] = toml_edit::value("target");
+        fs::create_dir_all(dir.join(".cargo"))?;
+        fs::write(&cargo_config_path, cargo_config.to_string())?;
+    }
+
+    let mut manifest =
+        "[workspace]\nresolver = \"2\"\nmembers = []\n".parse::<toml_edit::Document>()?;
+    manifest["profile"] = implicit_table();
+    manifest["profile"]["release"] = conf_preserved["profile"]["release"].clone();
+    fs::write(&manifest_path, manifest.to_string())?;
+
+    let templates_dir = dir.join("templates");
+    fs::create_dir_all(&templates_dir)?;
+    let template_path = templates_dir.join("main.rs");
+    if !template_path.exists() {
+        fs::write(&template_path, &config.project.template)?;
+    }
+
+    let rustc_version = match opt
+        .rustc_version
+        .or_else(|| config.project.rustc_version.clone())
+    {
+        Some(rustc_version) => Some(rustc_version),
+        None => judge_rustc_version().await,
+    };
+    match &rustc_version {
+        Some(rustc_version) => fs::write(dir.join("rust-toolchain"), rustc_version)?,
+        None => println!(
+            "`rust-toolchain` is not created. Specify the Rust version of the judge shown in \
+             `cargo atcoder languages` with `--rustc-version` or `project.rustc_version`."
+        ),
+    }
+
+    println!("Initialized the contest root `{}`.", dir.display());
+
+    if !opt.skip_warmup {
+        prebuild(
+            &config,
+            &conf_preserved,
+            dir,
+            &manifest,
+            rustc_version.as_deref(),
+        )?;
+        println!("Warming up done.");
+    }
+    Ok(())
+}
+
+/// The version of rustc on the judge, read from the submit form of the practice contest.
+/// `None` if it cannot be read, e.g. because the session is not logged in.
+async fn judge_rustc_version() -> Option<String> {
+    const CONTEST_ID: &str = "practice";
+
+    let result = async {
+        // Not to access the network only to find that the session is not logged in.
+        let session_file = session_file()?;
+        if !session_file.exists() {
+            return Ok(None);
+        }
+        let atc = AtCoder::new(&session_file)?;
+        if atc.username().await?.is_none() {
+            return Ok(None);
+        }
+        let contest_info = atc.contest_info(CONTEST_ID).await?;
+        let problem = contest_info
+            .problem_ids_lowercase()
+            .first()
+            .and_then(|id| contest_info.problem(id))
+            .with_context(|| format!("`{}` has no problems", CONTEST_ID))?;
+        let languages = atc.languages(CONTEST_ID, problem).await?;
+        Ok::<_, anyhow::Error>(
+            default_language(&languages)
+                .and_then(atcoder::rustc_version)
+                .map(ToOwned::to_owned),
+        )
+    }
+    .await;
+
+    match result {
+        Ok(Some(rustc_version)) => {
+            println!("The judge uses rustc {}.", rustc_version);
+            Some(rustc_version)
+        }
+        Ok(None) => None,
+        Err(err) => {
+            eprintln!("Failed to get the Rust version of the judge: {:#}", err);
+            None
+        }
+    }
+}
+
+/// Builds the dependencies for debug, release and `profile.target` in the workspace at `dir`,
+/// so that they are reused by members added later. The builds use `rustc_version` if given.
+fn prebuild(
+    config: &Config,
+    conf_preserved: &toml_edit::Document,
+    dir: &Path,
+    manifest: &toml_edit::Document,
+    rustc_version: Option<&str>,
+) -> Result<()> {
+    let manifest_path = dir.join("Cargo.toml");
+    let member_dir = dir.join(PREBUILD_MEMBER);
+
+    let result = prebuild_with_member(
+        config,
+        conf_preserved,
+        &manifest_path,
+        &member_dir,
+        manifest,
+        rustc_version,
+    );
+
+    // Restore the workspace even if the member could not be set up or built.
+    let restored = fs::write(&manifest_path, manifest.to_string())
+        .with_context(|| format!("Failed to restore {}", manifest_path.display()));
+    let removed = match fs::remove_dir_all(&member_dir) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        removed => removed.with_context(|| format!("Failed to remove {}", member_dir.display())),
+    };
+    result.and(restored).and(removed)
+}
+
+fn prebuild_with_member(
+    config: &Config,
+    conf_preserved: &toml_edit::Document,
+    manifest_path: &Path,
+    member_dir: &Path,
+    manifest: &toml_edit::Document,
+    rustc_version: Option<&str>,
+) -> Result<()> {
+    let member_src_dir = member_dir.join("src");
+    fs::create_dir_all(&member_src_dir)
+        .with_context(|| format!("Failed to create {}", member_src_dir.display()))?;
+    fs::write(member_src_dir.join("main.rs"), "fn main() {}\n")?;
+    let mut member_manifest =
+        "[package]\nname = \"prebuild\"\nversion = \"0.1.0\"\nedition = \"2018\"\n"
+            .parse::<toml_edit::Document>()?;
+    member_manifest["dependencies"] = conf_preserved["dependencies"].clone();
+    fs::write(member_dir.join("Cargo.toml"), member_manifest.to_string())?;
+
+    let mut with_member = manifest.clone();
+    with_member["workspace"]["members"]
+        .as_value_mut()
+        .and_then(toml_edit::Value::as_array_mut)
+        .with_context(|| "`workspace.members` should be an array")?
+        .push(PREBUILD_MEMBER)
+        .map_err(|_| anyhow!("`workspace.members` should be an array of strings"))?;
+    fs::write(manifest_path, with_member.to_string())
+        .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+
+    let target = &config.profile.target;
+    let program = if config.atcoder.use_cross {
+        "cross"
     } else {
-        let atc = AtCoder::new(&session_file()?)?;
+        "cargo"
+    };
+    let builds = [
+        ("debug", "cargo", vec![]),
+        ("release", "cargo", vec!["--release".to_owned()]),
+        (
+            target.as_str(),
+            program,
+            vec!["--release".to_owned(), format!("--target={}", target)],
+        ),
+    ];
+    let mut failed = vec![];
+    for (name, program, args) in &builds {
+        println!("Pre-building dependencies for {}...", name);
+        let mut cmd = Command::new(program);
+        cmd.arg("build").args(args).current_dir(member_dir);
+        if let Some(rustc_version) = rustc_version {
+            cmd.env("RUSTUP_TOOLCHAIN", rustc_version);
+        }
+        let stat = cmd
+            .status()
+            .with_context(|| format!("Failed to run `{}`", program))?;
+        if !stat.success() {
+            failed.push(*name);
+        }
+    }
+    ensure!(
+        failed.is_empty(),
+        "Failed to pre-build the dependencies for {}",
+        failed.join(", "),
+    );
+    Ok(())
+}
 
-        match atc.contest_info(&opt.contest_id).await {
-            Ok(info) => info.problem_ids_lowercase(),
-            Err(err) if http::is_http_error(&err, reqwest::StatusCode::NOT_FOUND) => atc
-                .problem_ids_from_score_table(&opt.contest_id)
+/// Bin names of the contest, which are `bins` if not empty.
+async fn problem_bins(
+    atc: &AtCoder,
+    contest_id: &str,
+    bins: Vec<String>,
+    wait: bool,
+) -> Result<(Vec<String>, Option<ContestInfo>)> {
+    if !bins.is_empty() {
+        return Ok((bins, None));
+    }
+
+    let info = if wait {
+        wait_for_contest_start(atc, contest_id).await?;
+        atc.contest_info_after_start(contest_id).await
+    } else {
+        atc.contest_info(contest_id).await
+    };
+
+    match info {
+        Ok(info) => Ok((info.problem_ids_lowercase(), Some(info))),
+        Err(err) if http::is_http_error(&err, reqwest::StatusCode::NOT_FOUND) => {
+            let bins = atc
+                .problem_ids_from_score_table(contest_id)
                 .await?
                 .map(|ss| ss.iter().map(|s| s.to_lowercase()).collect())
                 .with_context(|| {
                     err.context("could not find problem names. please specify names with `--bins`")
-                })?,
-            Err(err) => Err(err)?,
+                })?;
+            Ok((bins, None))
         }
-    };
+        Err(err) => Err(err),
+    }
+}
 
-    let dir = Path::new(&opt.contest_id);
+/// The bins and the extra files of a new package, rendered from the templates.
+async fn package_files(
+    atc: &AtCoder,
+    templates: &Templates<'_>,
+    contest_id: &str,
+    bins: &[String],
+    contest_info: Option<&ContestInfo>,
+    doc_comment: Option<statement::Lang>,
+) -> Result<Vec<(PathBuf, String)>> {
+    if doc_comment.is_some() && contest_info.is_none() {
+        eprintln!("Problems are not known. Skipping doc comments.");
+    }
+
+    let vars = template::vars(contest_id, None, None, None);
+    let mut files = templates
+        .extra_files(contest_id)?
+        .into_iter()
+        .map(|(path, content)| (path, template::render(&content, &vars)))
+        .collect::<Vec<_>>();
+
+    let bin_paths = bins
+        .iter()
+        .map(|bin| Path::new("src").join("bin").join(bin).with_extension("rs"))
+        .collect::<Vec<_>>();
+    if let Some((path, _)) = files
+        .iter()
+        .find(|(path, _)| path == Path::new("Cargo.toml") || bin_paths.contains(path))
+    {
+        bail!(
+            "The extra file `{}` in the templates conflicts with a file generated by cargo-atcoder",
+            path.display(),
+        );
+    }
+
+    for (bin, bin_path) in bins.iter().zip(bin_paths) {
+        let template = templates.bin(contest_id, bin)?;
+        let problem = contest_info.and_then(|info| info.problem(bin));
+        // Fetched once for both the samples and the statement.
+        let page = match problem {
+            Some(problem) if doc_comment.is_some() || template::uses(&template, "sample_count") => {
+                match atc.task_page(problem).await {
+                    Ok(page) => Some(page),
+                    Err(err) => {
+                        eprintln!("Failed to fetch the task page of `{}`: {}", bin, err);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        let test_cases = page.as_ref().and_then(|page| match page.test_cases() {
+            Ok(test_cases) => Some(test_cases),
+            Err(err) => {
+                eprintln!("Failed to scrape samples for `{}`: {}", bin, err);
+                None
+            }
+        });
+        let vars = template::vars(
+            contest_id,
+            Some(bin),
+            problem,
+            test_cases.as_ref().map(Vec::len),
+        );
+        let mut source = template::render(&template, &vars);
+        if let (Some(lang), Some(problem)) = (doc_comment, problem) {
+            let statement = page
+                .as_ref()
+                .and_then(|page| match page.statement(problem, lang) {
+                    Ok(statement) => Some(statement),
+                    Err(err) => {
+                        eprintln!("Failed to scrape the statement of `{}`: {}", bin, err);
+                        None
+                    }
+                });
+            source = format!(
+                "{}\n{}",
+                statement::to_doc_comment(
+                    problem,
+                    statement.as_ref(),
+                    test_cases.as_deref().unwrap_or_default(),
+                    lang,
+                ),
+                source,
+            );
+        }
+        files.push((bin_path, source));
+    }
+    Ok(files)
+}
+
+/// Creates a package at `dir` with `files` and the dependencies in the config.
+fn create_package(
+    config: &Config,
+    dir: &Path,
+    files: &[(PathBuf, String)],
+    member: bool,
+) -> Result<()> {
     if dir.is_dir() || dir.is_file() {
         bail!("Directory {} already exists", dir.display());
     }
 
     let stat = Command::new("cargo")
         .arg("new")
-        .arg(&opt.contest_id)
+        .args(if member { &["--vcs", "none"][..] } else { &[] })
+        .arg(dir)
         .status()?;
     if !stat.success() {
-        bail!("Failed to create project: {}", &opt.contest_id);
+        bail!("Failed to create project: {}", dir.display());
     }
 
-    if let Some(rustc_version) = &config.project.rustc_version {
-        fs::write(dir.join("rust-toolchain"), rustc_version)?;
+    // A member uses the toolchain of the workspace.
+    if !member {
+        if let Some(rustc_version) = &config.project.rustc_version {
+            fs::write(dir.join("rust-toolchain"), rustc_version)?;
+        }
     }
 
     fs::remove_file(dir.join("src").join("main.rs"))?;
     fs::create_dir(dir.join("src").join("bin"))?;
 
-    for bin in bins {
-        fs::write(
-            dir.join("src").join("bin").join(bin).with_extension("rs"),
-            &config.project.template,
-        )?;
+    for (path, content) in files {
+        let path = dir.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
     }
 
     let toml_file = dir.join("Cargo.toml");
@@ -125,27 +676,177 @@ async fn new_project(opt: NewOpt) -> Result<()> {
     let conf_preserved = read_config_preserving()?;
     manifest["dependencies"] = conf_preserved["dependencies"].clone();
     manifest["dev-dependencies"] = conf_preserved["dev-dependencies"].clone();
-    manifest["profile"] = toml_edit::Item::Table({
-        let mut tbl = toml_edit::Table::new();
-        tbl.set_implicit(true);
-        tbl
-    });
-    manifest["profile"]["release"] = conf_preserved["profile"]["release"].clone();
-
+    // Profiles of a member are ignored.
+    if !member {
+        manifest["profile"] = implicit_table();
+        manifest["profile"]["release"] = conf_preserved["profile"]["release"].clone();
+    }
     fs::write(toml_file, manifest.to_string())?;
+    Ok(())
+}
 
-    println!("Creating project done.");
+fn implicit_table() -> toml_edit::Item {
+    let mut tbl = toml_edit::Table::new();
+    tbl.set_implicit(true);
+    toml_edit::Item::Table(tbl)
+}
 
-    if !opt.skip_warmup {
-        let metadata = metadata::cargo_metadata(None, format!("./{}", opt.contest_id).as_ref())?;
-        warmup_for(&metadata, Some(&[&opt.contest_id]))?;
-        println!("Warming up done.");
+/// Offers registration for a contest found on AtCoder, which may not have started yet.
+/// The package has already been created, so a failure is only reported.
+async fn offer_registration(atc: &AtCoder, contest_id: &str) {
+    let result = async {
+        if atc.username().await?.is_some() {
+            ensure_registered(atc, contest_id).await?;
+        }
+        Ok::<_, anyhow::Error>(())
+    }
+    .await;
+    if let Err(err) = result {
+        eprintln!("Did not register for `{}`: {:#}", contest_id, err);
     }
+}
 
+async fn wait_for_contest_start(atc: &AtCoder, contest_id: &str) -> Result<()> {
+    let schedule = atc.contest_schedule(contest_id).await?;
+    if schedule.started() {
+        return Ok(());
+    }
+
+    let start_time = DateTime::<Local>::from(schedule.start).format("%Y-%m-%d %H:%M:%S");
+    let pb = ProgressBar::new_spinner()
+        .with_style(ProgressStyle::default_spinner().template("{spinner:.cyan} {msg}"));
+    // Short steps recomputed from the clock, so that a suspend does not delay the start.
+    loop {
+        let remaining = schedule.start - Utc::now();
+        if remaining <= chrono::Duration::zero() {
+            break;
+        }
+        pb.set_message(format!(
+            "`{}` starts at {} (in {})",
+            contest_id,
+            start_time,
+            format_duration(remaining),
+        ));
+        pb.tick();
+        let step = remaining
+            .to_std()
+            .unwrap_or_default()
+            .min(Duration::from_secs(1));
+        sleep(step).await;
+    }
+    pb.finish_and_clear();
+    println!("`{}` has started.", contest_id);
+    Ok(())
+}
+
+fn format_duration(d: chrono::Duration) -> String {
+    let secs = max(0, d.num_seconds());
+    let (days, secs) = (secs / 86400, secs % 86400);
+    let hms = format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    if days > 0 {
+        format!("{}d {}", days, hms)
+    } else {
+        hms
+    }
+}
+
+#[derive(StructOpt)]
+struct RegisterOpt {
+    /// Contest ID (e.g. abc123)
+    contest_id: String,
+}
+
+async fn register(opt: RegisterOpt) -> Result<()> {
+    let atc = AtCoder::new(&session_file()?)?;
+    atc.register(&opt.contest_id).await?;
+    println!("Registered for `{}`.", opt.contest_id);
+    Ok(())
+}
+
+/// Offers registration if the user has not registered for the contest yet.
+async fn ensure_registered(atc: &AtCoder, contest_id: &str) -> Result<()> {
+    if !atc.needs_registration(contest_id).await? {
+        return Ok(());
+    }
+    if !console::user_attended() {
+        bail!(error::Error::NotRegistered {
+            contest_id: contest_id.to_owned(),
+        });
+    }
+
+    let confirmed = dialoguer::Confirm::new()
+        .with_prompt(format!(
+            "You are not registered for `{}`. Register now?",
+            contest_id
+        ))
+        .default(true)
+        .interact()?;
+
+    if !confirmed {
+        bail!(error::Error::NotRegistered {
+            contest_id: contest_id.to_owned(),
+        });
+    }
+    atc.register(contest_id).await?;
+    println!("Registered for `{}`.", contest_id);
     Ok(())
 }
 
-async fn login() -> Result<()> {
+/// Finds the problem solved by the bin `bin_name`: the task given in
+/// `[package.metadata.cargo-atcoder.bin.<bin_name>]`, or else the problem whose ID is
+/// `bin_name`.
+fn find_problem<'a>(
+    package: &Package,
+    contest_id: &str,
+    contest_info: &'a ContestInfo,
+    bin_name: &str,
+) -> Result<&'a Problem> {
+    let (problem, problem_id) = match package.task_screen_name(bin_name)? {
+        Some(task) => (contest_info.problem_by_task(&task), task),
+        None => (contest_info.problem(bin_name), bin_name.to_owned()),
+    };
+    problem.ok_or_else(|| {
+        error::Error::ProblemNotFound {
+            contest_id: contest_id.to_owned(),
+            problem_id,
+        }
+        .into()
+    })
+}
+
+#[derive(StructOpt)]
+struct LoginOpt {
+    /// Import the session cookie (`REVEL_SESSION=...` or its value) copied from a browser
+    #[structopt(
+        long,
+        value_name("COOKIE"),
+        env = "CARGO_ATCODER_REVEL_SESSION",
+        hide_env_values = true
+    )]
+    cookie: Option<String>,
+    /// Import the session cookie from a file (the value or a Netscape cookies.txt)
+    #[structopt(long, value_name("PATH"))]
+    from_file: Option<PathBuf>,
+}
+
+async fn login(opt: LoginOpt) -> Result<()> {
+    // `--from-file` wins over `--cookie` so that it can override the environment variable.
+    let cookie = match (&opt.from_file, &opt.cookie) {
+        (Some(path), _) => Some(
+            fs::read_to_string(path)
+                .with_context(|| format!("Failed to read `{}`", path.display()))?,
+        ),
+        (None, Some(cookie)) => Some(cookie.clone()),
+        (None, None) => None,
+    };
+    let atc = AtCoder::new(&session_file()?)?;
+    if let Some(cookie) = cookie {
+        return login_with_cookie(&atc, &parse_revel_session(&cookie)?).await;
+    }
+    login_with_password(&atc).await
+}
+
+async fn login_with_password(atc: &AtCoder) -> Result<()> {
     let username = dialoguer::Input::<String>::new()
         .with_prompt("Username")
         .interact()?;
@@ -154,7 +855,6 @@ async fn login() -> Result<()> {
         .with_prompt("Password")
         .interact()?;
 
-    let atc = AtCoder::new(&session_file()?)?;
     atc.login(&username, &password).await?;
 
     println!("Login succeeded.");
@@ -162,14 +862,99 @@ async fn login() -> Result<()> {
     Ok(())
 }
 
-fn clear_session() -> Result<()> {
-    let path = session_file()?;
-    if path.is_file() {
-        fs::remove_file(&path)?;
+/// Checks the session before doing any work, and asks to log in again if it has expired.
+async fn ensure_logged_in(atc: &AtCoder) -> Result<()> {
+    if atc.username().await?.is_some() {
+        return Ok(());
+    }
+    if !console::user_attended() {
+        bail!(error::Error::NotLoggedIn);
     }
+
+    println!("You are not logged in, or the session has expired.");
+    let choice = dialoguer::Select::new()
+        .with_prompt("Login with")
+        .items(&["Username and password", "Session cookie (REVEL_SESSION)"])
+        .default(0)
+        .interact()?;
+
+    if choice == 0 {
+        login_with_password(atc).await
+    } else {
+        let cookie = dialoguer::Password::new()
+            .with_prompt("REVEL_SESSION")
+            .interact()?;
+        login_with_cookie(atc, &parse_revel_session(&cookie)?).await
+    }
+}
+
+/// Accepts `REVEL_SESSION=<value>`, a bare value, or a Netscape cookies.txt. A bare value
+/// is taken as it is, since it is URL-encoded and may contain `=`.
+fn parse_revel_session(s: &str) -> Result<String> {
+    let s = s.trim();
+    let is_cookies_txt = s.lines().any(|line| {
+        line.starts_with("# Netscape HTTP Cookie File") || line.split('\t').count() == 7
+    });
+    if is_cookies_txt {
+        return s
+            .lines()
+            .map(|line| line.split('\t').collect::<Vec<_>>())
+            .find(|fields| fields.len() == 7 && fields[5] == "REVEL_SESSION")
+            .map(|fields| fields[6].trim().to_owned())
+            .with_context(|| "`REVEL_SESSION` cookie not found");
+    }
+
+    let value = match s.strip_prefix("REVEL_SESSION=") {
+        Some(value) => value.split(';').next().unwrap_or_default().trim(),
+        None => s,
+    };
+    ensure!(!value.is_empty(), "`REVEL_SESSION` cookie not found");
+    Ok(value.to_owned())
+}
+
+async fn login_with_cookie(atc: &AtCoder, revel_session: &str) -> Result<()> {
+    let username = atc
+        .login_with_cookie(revel_session)
+        .await
+        .with_context(|| "Login failed")?;
+    println!("Login succeeded as `{}`.", username);
+    Ok(())
+}
+
+async fn logout() -> Result<()> {
+    let path = session_file()?;
+    let atc = AtCoder::new(&path)?;
+
+    let acknowledged = match atc.username().await? {
+        Some(username) => {
+            let acknowledged = atc.logout().await?;
+            if acknowledged {
+                println!("Logged out `{}` from AtCoder.", username);
+            } else {
+                println!(
+                    "AtCoder did not acknowledge the logout of `{}`. The session may still be valid on the server.",
+                    username
+                );
+            }
+            acknowledged
+        }
+        None => {
+            println!("Not logged in on AtCoder.");
+            true
+        }
+    };
+
+    clear_session()?;
+    println!("Removed the local session.");
+
+    ensure!(acknowledged, "Logout was not acknowledged by the server");
     Ok(())
 }
 
+fn clear_session() -> Result<()> {
+    http::remove_session(&session_file()?)
+}
+
 #[derive(StructOpt)]
 struct TestOpt {
     /// Problem ID (e.g. a, b, ...)
@@ -189,6 +974,9 @@ struct TestOpt {
     /// Submit if test passed
     #[structopt(short, long)]
     submit: bool,
+    /// Language ID or name used for submission (overwrite config)
+    #[structopt(long, value_name("LANG"), requires = "submit")]
+    language: Option<String>,
     /// [cargo build] Use --release flag to compile
     #[structopt(long)]
     release: bool,
@@ -203,18 +991,27 @@ async fn test(opt: TestOpt) -> Result<()> {
     let package = metadata.query_for_member(opt.package.as_deref())?;
     let atc = AtCoder::new(&session_file()?)?;
     let problem_id = opt.problem_id;
-    let contest_id = &package.name;
-    let contest_info = atc.contest_info(contest_id).await?;
-
-    let problem = contest_info
-        .problem(&problem_id)
-        .with_context(|| format!("Problem `{}` is not contained in this contest", &problem_id))?;
-
-    if opt.custom {
-        return test_custom(package, &problem_id, opt.release);
+    let contest_id = &package.contest_id()?;
+    if opt.submit {
+        ensure_logged_in(&atc).await?;
     }
-
-    let test_cases = atc.test_cases(&problem.url).await?;
+    let contest_info;
+    let (problem, test_cases) = match embedded_samples(package, &problem_id) {
+        // The problem is not needed when testing with the embedded samples only.
+        Some(test_cases) if !opt.submit && !opt.custom => (None, test_cases),
+        embedded_samples => {
+            contest_info = atc.contest_info(contest_id).await?;
+            let problem = find_problem(package, contest_id, &contest_info, &problem_id)?;
+            if opt.custom {
+                return test_custom(package, &problem_id, opt.release);
+            }
+            let test_cases = match embedded_samples {
+                Some(test_cases) => test_cases,
+                None => fetch_samples(&atc, problem).await?,
+            };
+            (Some(problem), test_cases)
+        }
+    };
 
     for &cn in opt.case_num.iter() {
         if cn == 0 || cn > test_cases.len() {
@@ -234,16 +1031,42 @@ async fn test(opt: TestOpt) -> Result<()> {
     }
 
     let passed = test_samples(package, &problem_id, &tcs, opt.release, opt.verbose)?;
-    if passed && opt.submit {
+    let submit = passed && opt.submit;
+    if let Some(problem) = problem.filter(|_| submit) {
+        ensure_registered(&atc, contest_id).await?;
+        let config = read_config()?;
         let Target { src_path, .. } = package.find_bin(&problem_id)?;
         let source = fs::read(src_path).with_context(|| format!("Failed to read {}", src_path))?;
-        atc.submit(contest_id, &problem_id, &String::from_utf8_lossy(&source))
-            .await?;
+        atc.submit(
+            contest_id,
+            problem,
+            &String::from_utf8_lossy(&source),
+            opt.language
+                .as_deref()
+                .or(config.atcoder.language.as_deref()),
+        )
+        .await?;
     }
 
     Ok(())
 }
 
+/// The samples embedded in the source of `bin` with `--doc-comment`.
+fn embedded_samples(package: &Package, bin: &str) -> Option<Vec<TestCase>> {
+    let Target { src_path, .. } = package.find_bin(bin).ok()?;
+    let source = fs::read_to_string(src_path).ok()?;
+    let test_cases = statement::read_doc_comment_samples(&source)?;
+    // To stderr, as the test results are on stdout.
+    eprintln!("Using the samples in the doc comment of `{}`", src_path);
+    Some(test_cases)
+}
+
+async fn fetch_samples(atc: &AtCoder, problem: &Problem) -> Result<Vec<TestCase>> {
+    let test_cases = atc.test_cases(&problem.url).await?;
+    eprintln!("Using the samples on {}", problem.full_url());
+    Ok(test_cases)
+}
+
 fn test_samples(
     package: &Package,
     problem_id: &str,
@@ -255,7 +1078,7 @@ fn test_samples(
         .arg("build")
         .args(if release { vec!["--release"] } else { vec![] })
         .arg("--bin")
-        .arg(&problem_id)
+        .arg(problem_id)
         .arg("--manifest-path")
         .arg(&package.manifest_path)
         .status()?;
@@ -279,7 +1102,7 @@ fn test_samples(
             .args(if release { vec!["--release"] } else { vec![] })
             .arg("-q")
             .arg("--bin")
-            .arg(&problem_id)
+            .arg(problem_id)
             .arg("--manifest-path")
             .arg(&package.manifest_path)
             .stdin(Stdio::piped())
@@ -469,7 +1292,7 @@ fn test_custom(package: &Package, problem_id: &str, release: bool) -> Result<()>
         .arg("build")
         .args(if release { vec!["--release"] } else { vec![] })
         .arg("--bin")
-        .arg(&problem_id)
+        .arg(problem_id)
         .arg("--manifest-path")
         .arg(&package.manifest_path)
         .status()?;
@@ -486,7 +1309,7 @@ fn test_custom(package: &Package, problem_id: &str, release: bool) -> Result<()>
         .args(if release { vec!["--release"] } else { vec![] })
         .arg("-q")
         .arg("--bin")
-        .arg(&problem_id)
+        .arg(problem_id)
         .arg("--manifest-path")
         .arg(&package.manifest_path)
         .stdout(Stdio::piped())
@@ -534,6 +1357,151 @@ fn print_lines(s: &str) {
     }
 }
 
+#[derive(StructOpt)]
+struct ShowOpt {
+    /// Problem ID (e.g. a, b, ...)
+    problem_id: String,
+    /// Language of the statement (ja or en)
+    #[structopt(long, default_value = "ja")]
+    lang: statement::Lang,
+    /// Write the statement as Markdown to the file
+    #[structopt(short, long, value_name("PATH"))]
+    output: Option<PathBuf>,
+    /// [cargo] Package
+    #[structopt(short, long, value_name("SPEC"))]
+    package: Option<String>,
+    /// [cargo] Path to Cargo.toml
+    #[structopt(long, value_name("PATH"))]
+    manifest_path: Option<PathBuf>,
+}
+
+async fn show(opt: ShowOpt) -> Result<()> {
+    let cwd = env::current_dir().with_context(|| "failed to get CWD")?;
+    let metadata = metadata::cargo_metadata(opt.manifest_path.as_deref(), &cwd)?;
+    let package = metadata.query_for_member(opt.package.as_deref())?;
+    let atc = AtCoder::new(&session_file()?)?;
+    let contest_id = &package.contest_id()?;
+    let contest_info = atc.contest_info(contest_id).await?;
+    let problem = find_problem(package, contest_id, &contest_info, &opt.problem_id)?;
+
+    let statement = atc.problem_statement(problem, opt.lang).await?;
+
+    if let Some(output) = &opt.output {
+        fs::write(output, statement::to_markdown(&statement))?;
+        println!("Wrote statement to `{}`", output.display());
+    } else {
+        statement::print_statement(&statement);
+    }
+    Ok(())
+}
+
+#[derive(StructOpt)]
+struct LanguagesOpt {
+    /// Problem ID (e.g. a, b, ...)
+    problem_id: String,
+    /// [cargo] Package
+    #[structopt(short, long, value_name("SPEC"))]
+    package: Option<String>,
+    /// [cargo] Path to Cargo.toml
+    #[structopt(long, value_name("PATH"))]
+    manifest_path: Option<PathBuf>,
+}
+
+async fn languages(opt: LanguagesOpt) -> Result<()> {
+    let cwd = env::current_dir().with_context(|| "failed to get CWD")?;
+    let metadata = metadata::cargo_metadata(opt.manifest_path.as_deref(), &cwd)?;
+    let package = metadata.query_for_member(opt.package.as_deref())?;
+    let atc = AtCoder::new(&session_file()?)?;
+    let config = read_config()?;
+
+    let contest_id = &package.contest_id()?;
+    let contest_info = atc.contest_info(contest_id).await?;
+    let problem = find_problem(package, contest_id, &contest_info, &opt.problem_id)?;
+
+    let languages = atc.languages(contest_id, problem).await?;
+    let selected = match &config.atcoder.language {
+        Some(spec) => Some(find_language(&languages, spec)?),
+        None => default_language(&languages),
+    };
+
+    for lang in &languages {
+        let mark = if selected == Some(lang) { "*" } else { " " };
+        println!("{} {:>5} | {}", mark, lang.id, lang.name);
+    }
+    Ok(())
+}
+
+#[derive(StructOpt)]
+struct EditorialOpt {
+    /// Problem ID (e.g. a, b, ...)
+    problem_id: String,
+    /// Save the official editorial as Markdown next to the bin source
+    #[structopt(short, long)]
+    save: bool,
+    /// [cargo] Package
+    #[structopt(short, long, value_name("SPEC"))]
+    package: Option<String>,
+    /// [cargo] Path to Cargo.toml
+    #[structopt(long, value_name("PATH"))]
+    manifest_path: Option<PathBuf>,
+}
+
+async fn editorial(opt: EditorialOpt) -> Result<()> {
+    let cwd = env::current_dir().with_context(|| "failed to get CWD")?;
+    let metadata = metadata::cargo_metadata(opt.manifest_path.as_deref(), &cwd)?;
+    let package = metadata.query_for_member(opt.package.as_deref())?;
+    let atc = AtCoder::new(&session_file()?)?;
+    let contest_id = &package.contest_id()?;
+    let contest_info = atc.contest_info(contest_id).await?;
+    let problem = find_problem(package, contest_id, &contest_info, &opt.problem_id)?;
+
+    let editorials = atc.editorials(problem).await?;
+    if editorials.is_empty() {
+        println!("No editorials found for `{}`.", opt.problem_id);
+        return Ok(());
+    }
+
+    let cyan = Style::new().cyan();
+    for e in &editorials {
+        println!(
+            "[{}] {}{} {}",
+            match e.kind {
+                EditorialKind::Official => "official",
+                EditorialKind::User => "user",
+            },
+            e.title,
+            e.author
+                .as_ref()
+                .map(|a| format!(" by {}", a))
+                .unwrap_or_default(),
+            cyan.apply_to(e.full_url()),
+        );
+    }
+
+    let official = match editorials
+        .iter()
+        .find(|e| e.kind == EditorialKind::Official && !e.is_external())
+    {
+        Some(official) => official,
+        None => return Ok(()),
+    };
+    let blocks = atc.editorial_content(official).await?;
+
+    println!();
+    statement::print_blocks(&blocks);
+
+    if opt.save {
+        let Target { src_path, .. } = package.find_bin(&opt.problem_id)?;
+        let path = Path::new(src_path).with_extension("editorial.md");
+        let mut md = format!("# [{}]({})\n", official.title, official.full_url());
+        statement::write_markdown_blocks(&mut md, &blocks);
+        fs::write(&path, md)?;
+        println!();
+        println!("Saved the editorial to `{}`", path.display());
+    }
+    Ok(())
+}
+
 #[derive(StructOpt)]
 struct SubmitOpt {
     /// Problem ID (must be same as binary name)
@@ -561,6 +1529,9 @@ struct SubmitOpt {
     /// Do no use upx unless available
     #[structopt(long)]
     no_upx: bool,
+    /// Language ID or name used for submission (overwrite config)
+    #[structopt(long, value_name("LANG"))]
+    language: Option<String>,
     /// [cargo build] Use --release on pre-test (submission always uses --release)
     #[structopt(long)]
     release: bool,
@@ -573,22 +1544,21 @@ async fn submit(opt: SubmitOpt) -> Result<()> {
     let atc = AtCoder::new(&session_file()?)?;
     let config = read_config()?;
 
-    let contest_id = &package.name;
+    let contest_id = &package.contest_id()?;
     let problem_id = opt.problem_id;
+    ensure_logged_in(&atc).await?;
+    ensure_registered(&atc, contest_id).await?;
     let contest_info = atc.contest_info(contest_id).await?;
-    let problem = contest_info
-        .problem(&problem_id)
-        .with_context(|| format!("Problem `{}` is not contained in this contest", &problem_id))?;
+    let problem = find_problem(package, contest_id, &contest_info, &problem_id)?;
 
     let test_passed = if opt.skip_test {
         true
     } else {
-        let test_cases = atc
-            .test_cases(&problem.url)
-            .await?
-            .into_iter()
-            .enumerate()
-            .collect::<Vec<_>>();
+        let test_cases = match embedded_samples(package, &problem_id) {
+            Some(test_cases) => test_cases,
+            None => fetch_samples(&atc, problem).await?,
+        };
+        let test_cases = test_cases.into_iter().enumerate().collect::<Vec<_>>();
         test_samples(package, &problem_id, &test_cases, opt.release, false)?
     };
 
@@ -604,16 +1574,23 @@ async fn submit(opt: SubmitOpt) -> Result<()> {
         fs::read(src_path).with_context(|| format!("Failed to read {}", src_path))?
     } else {
         println!("Submitting via binary...");
-        gen_binary_source(&metadata, package, &target, &config, opt.column, opt.no_upx)?
+        gen_binary_source(&metadata, package, target, &config, opt.column, opt.no_upx)?
     };
 
-    atc.submit(contest_id, &problem_id, &String::from_utf8_lossy(&source))
-        .await?;
+    atc.submit(
+        contest_id,
+        problem,
+        &String::from_utf8_lossy(&source),
+        opt.language
+            .as_deref()
+            .or(config.atcoder.language.as_deref()),
+    )
+    .await?;
     println!();
 
     println!("Fetching submission result...");
     let atc = Arc::new(atc);
-    let last_id = watch_submission_status(Arc::clone(&atc), contest_id, true).await?;
+    let last_id = watch_submission_status(Arc::clone(&atc), contest_id, true, None, None).await?;
     println!();
 
     if let Some(last_id) = last_id {
@@ -775,6 +1752,86 @@ async fn info() -> Result<()> {
     Ok(())
 }
 
+#[derive(StructOpt)]
+struct ContestsOpt {
+    /// Show upcoming contests
+    #[structopt(long)]
+    upcoming: bool,
+    /// Show running contests
+    #[structopt(long)]
+    running: bool,
+    /// Show recently finished contests
+    #[structopt(long)]
+    recent: bool,
+    /// Output in JSON
+    #[structopt(long)]
+    json: bool,
+}
+
+async fn contests(opt: ContestsOpt) -> Result<()> {
+    let atc = AtCoder::new(&session_file()?)?;
+    let all = !opt.upcoming && !opt.running && !opt.recent;
+    let contests = atc
+        .contests()
+        .await?
+        .into_iter()
+        .filter(|c| {
+            all || match c.status {
+                ContestStatus::Running => opt.running,
+                ContestStatus::Upcoming => opt.upcoming,
+                ContestStatus::Recent => opt.recent,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if opt.json {
+        println!("{}", serde_json::to_string_pretty(&contests)?);
+        return Ok(());
+    }
+
+    let id_width = contests.iter().map(|c| c.id.width()).max().unwrap_or(0);
+    let bold = Style::new().bold();
+    println!(
+        "{}",
+        bold.apply_to(format!(
+            "{:8} | {:16} | {:>8} | {:11} | {:9} | {:id_width$} | Title",
+            "Status",
+            "Start",
+            "Duration",
+            "Rated",
+            "Type",
+            "ID",
+            id_width = id_width,
+        ))
+    );
+    for c in contests {
+        println!(
+            "{:8} | {:16} | {:>8} | {:11} | {:9} | {:id_width$} | {}",
+            match c.status {
+                ContestStatus::Running => "running",
+                ContestStatus::Upcoming => "upcoming",
+                ContestStatus::Recent => "recent",
+            },
+            DateTime::<Local>::from(c.start).format("%Y-%m-%d %H:%M"),
+            format!(
+                "{:02}:{:02}",
+                c.duration_minutes / 60,
+                c.duration_minutes % 60
+            ),
+            c.rated_range.to_string(),
+            match &c.contest_type {
+                ContestType::Algorithm => "Algorithm",
+                ContestType::Heuristic => "Heuristic",
+                ContestType::Other(s) => s,
+            },
+            c.id,
+            c.title,
+            id_width = id_width,
+        );
+    }
+    Ok(())
+}
+
 #[derive(StructOpt, Debug)]
 struct WarmupOpt {
     /// [cargo] Package(s) to warm up
@@ -803,7 +1860,7 @@ fn warmup_for(metadata: &Metadata, specs: Option<&[impl AsRef<str>]>) -> Result<
         .unwrap_or_else(|| Ok(metadata.all_members()))?;
 
     for member in members {
-        if let Some(first_bin) = member.all_bins().get(0) {
+        if let Some(first_bin) = member.all_bins().first() {
             println!("Warming up debug build for `{}`...", member.name);
 
             let stat = Command::new("cargo")
@@ -837,12 +1894,17 @@ fn warmup_for(metadata: &Metadata, specs: Option<&[impl AsRef<str>]>) -> Result<
     Ok(())
 }
 
+const CLARIFICATION_INTERVAL: Duration = Duration::from_secs(30);
+
 async fn watch_submission_status(
     atc: Arc<AtCoder>,
     contest_id: &str,
     recent_only: bool,
+    contest_end: Option<DateTime<Utc>>,
+    clarifications: Option<SeenClarifications>,
 ) -> Result<Option<usize>> {
     let config = read_config()?;
+    let mut clarifications = clarifications.map(|seen| (seen, None::<Instant>));
     let cur_time = chrono::offset::Utc::now();
 
     let contest_id = contest_id.to_owned();
@@ -876,6 +1938,20 @@ async fn watch_submission_status(
         let green = Style::new().green();
         let red = Style::new().red();
 
+        let remaining_pb = contest_end.map(|end| {
+            let pb = m.add(ProgressBar::new_spinner().with_style(finish_style.clone()));
+            let update = move || {
+                let now = Utc::now();
+                pb.set_message(if now < end {
+                    format!("Contest ends in {}", format_duration(end - now))
+                } else {
+                    "Contest has ended".to_owned()
+                });
+            };
+            update();
+            update
+        });
+
         let mut last_id;
 
         loop {
@@ -892,6 +1968,24 @@ async fn watch_submission_status(
 
             last_id = results.iter().last().map(|r| r.id);
 
+            if let Some((seen, last_fetched)) = &mut clarifications {
+                let due = match last_fetched {
+                    Some(t) => t.elapsed() >= CLARIFICATION_INTERVAL,
+                    None => true,
+                };
+                if due {
+                    *last_fetched = Some(Instant::now());
+                    let lines = match seen.take_new(&atc, &contest_id).await {
+                        Ok(new) => new.iter().flat_map(format_clarification).collect(),
+                        Err(err) => vec![format!("Failed to fetch clarifications: {}", err)],
+                    };
+                    for line in lines {
+                        m.add(ProgressBar::new_spinner().with_style(finish_style.clone()))
+                            .finish_with_message(line);
+                    }
+                }
+            }
+
             let mut done = true;
 
             for result in results {
@@ -992,9 +2086,12 @@ async fn watch_submission_status(
             }
 
             let refresh_rate = 100;
-            let update_interval = max(1000, config.atcoder.update_interval);
+            let update_interval = update_interval(&config);
 
             for _ in 0..update_interval / refresh_rate {
+                if let Some(update) = &remaining_pb {
+                    update();
+                }
                 for (_, (pb, live)) in dat.iter() {
                     if *live {
                         pb.tick();
@@ -1010,7 +2107,11 @@ async fn watch_submission_status(
         ret
     });
 
-    Ok(join!(join_fut, update_fut).1??)
+    join!(join_fut, update_fut).1?
+}
+
+fn update_interval(config: &Config) -> u64 {
+    max(1000, config.atcoder.update_interval)
 }
 
 #[derive(StructOpt)]
@@ -1065,7 +2166,9 @@ async fn result(opt: ResultOpt) -> Result<()> {
     let cwd = env::current_dir().with_context(|| "failed to get CWD")?;
     let metadata = metadata::cargo_metadata(opt.manifest_path.as_deref(), &cwd)?;
     let atc = AtCoder::new(&session_file()?)?;
-    let contest_id = &metadata.query_for_member(opt.package.as_deref())?.name;
+    let contest_id = &metadata
+        .query_for_member(opt.package.as_deref())?
+        .contest_id()?;
     let res = atc
         .submission_status_full(contest_id, opt.submission_id)
         .await?;
@@ -1189,35 +2292,95 @@ async fn status(opt: StatusOpt) -> Result<()> {
     let cwd = env::current_dir().with_context(|| "failed to get CWD")?;
     let metadata = metadata::cargo_metadata(opt.manifest_path.as_deref(), &cwd)?;
     let atc = AtCoder::new(&session_file()?)?;
-    let contest_id = &metadata.query_for_member(opt.package.as_deref())?.name;
+    let contest_id = &metadata
+        .query_for_member(opt.package.as_deref())?
+        .contest_id()?;
+    let contest_end = match atc.contest_schedule(contest_id).await {
+        Ok(schedule) => Some(schedule.end),
+        Err(err) => {
+            eprintln!("Could not fetch the contest schedule: {}", err);
+            None
+        }
+    };
+    let clarifications = SeenClarifications::load(contest_id)?;
     let atc = Arc::new(atc);
-    watch_submission_status(atc, contest_id, false).await?;
+    watch_submission_status(atc, contest_id, false, contest_end, Some(clarifications)).await?;
     Ok(())
 }
 
+/// Logs of this crate are shown according to `-v`. `RUST_LOG` overrides it.
+fn init_tracing(verbose: u8) {
+    let level = match verbose {
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    };
+    let filter = env::var("RUST_LOG").unwrap_or_else(|_| format!("cargo_atcoder={}", level));
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::new(filter))
+        .with_writer(io::stderr)
+        .with_ansi(console::colors_enabled_stderr())
+        .without_time()
+        .init();
+}
+
 #[derive(StructOpt)]
 #[structopt(bin_name("cargo"))]
 enum Opt {
     #[structopt(name = "atcoder")]
-    AtCoder(OptAtCoder),
+    AtCoder(OptRoot),
+}
+
+#[derive(StructOpt)]
+struct OptRoot {
+    /// Profile (account) to use (must precede the subcommand)
+    #[structopt(long, value_name("NAME"), env = "CARGO_ATCODER_PROFILE")]
+    profile: Option<String>,
+    /// Log HTTP requests (-v) and debug information (-vv) to stderr
+    #[structopt(short, long, parse(from_occurrences))]
+    verbose: u8,
+    /// Dump every response body into the directory (must precede the subcommand)
+    #[structopt(long, value_name("DIR"))]
+    dump_responses: Option<PathBuf>,
+    #[structopt(subcommand)]
+    command: OptAtCoder,
 }
 
 #[derive(StructOpt)]
 enum OptAtCoder {
     /// Create a new project for specified contest
     New(NewOpt),
+    /// Set up a workspace to add contests to
+    Init(InitOpt),
+    /// Add a contest as a member of the current workspace
+    Add(AddOpt),
     /// Login to atcoder
-    Login,
-    // /// Logout from atcoder
-    // Logout,
+    Login(LoginOpt),
+    /// Register for a contest
+    Register(RegisterOpt),
+    /// Logout from atcoder
+    Logout,
     /// Clear session data (cookie store in HTTP client)
     ClearSession,
+    /// List profiles and their logged-in users
+    Profiles,
     /// Show session information
     Info,
+    /// Show user profile and rating history
+    User(user::UserOpt),
+    /// List upcoming, running and recent contests
+    Contests(ContestsOpt),
     /// Warmup (pre-compile dependencies)
     Warmup(WarmupOpt),
     /// Test sample cases
     Test(TestOpt),
+    /// Show problem statement
+    Show(ShowOpt),
+    /// Show editorials of a problem
+    Editorial(EditorialOpt),
+    /// List languages available for submission
+    Languages(LanguagesOpt),
     /// Submit solution
     Submit(SubmitOpt),
     /// Show submission result detail
@@ -1226,6 +2389,10 @@ enum OptAtCoder {
     GenBinary(GenBinaryOpt),
     /// Show submission status
     Status(StatusOpt),
+    /// Show contest standings
+    Standings(standings::StandingsOpt),
+    /// Estimate performances and rating changes
+    Perf(rating::PerfOpt),
 
     /// [WIP] Watch filesystem for automatic submission
     #[cfg(feature = "watch")]
@@ -1233,26 +2400,85 @@ enum OptAtCoder {
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let Opt::AtCoder(opt) = Opt::from_args();
+async fn main() {
+    if let Err(err) = run().await {
+        eprintln!("Error: {:?}", err);
+        process::exit(error::exit_code(&err));
+    }
+}
 
+async fn run() -> Result<()> {
+    let Opt::AtCoder(OptRoot {
+        profile,
+        verbose,
+        dump_responses,
+        command: opt,
+    }) = Opt::from_args();
+
+    init_tracing(verbose);
+    if let Some(dir) = &dump_responses {
+        http::set_dump_dir(dir)?;
+    }
+    profile::init(profile)?;
     let _ = read_config()?; // for checking config syntax
 
     use OptAtCoder::*;
     match opt {
         New(opt) => new_project(opt).await,
-        Login => login().await,
-        // Logout => unimplemented!(),
+        Init(opt) => init_workspace(opt).await,
+        Add(opt) => add_member(opt).await,
+        Login(opt) => login(opt).await,
+        Register(opt) => register(opt).await,
+        Logout => logout().await,
         ClearSession => clear_session(),
+        Profiles => profile::profiles().await,
         Info => info().await,
+        User(opt) => user::user(opt).await,
+        Contests(opt) => contests(opt).await,
         Warmup(opt) => warmup(opt),
         Test(opt) => test(opt).await,
+        Show(opt) => show(opt).await,
+        Editorial(opt) => editorial(opt).await,
+        Languages(opt) => languages(opt).await,
         Submit(opt) => submit(opt).await,
         Result(opt) => result(opt).await,
         GenBinary(opt) => gen_binary(opt),
         Status(opt) => status(opt).await,
+        Standings(opt) => standings::standings(opt).await,
+        Perf(opt) => rating::perf(opt).await,
 
         #[cfg(feature = "watch")]
         Watch(opt) => watch::watch(opt).await,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_revel_session_forms() {
+        assert_eq!(
+            parse_revel_session("REVEL_SESSION=a%3Db\n").unwrap(),
+            "a%3Db"
+        );
+        assert_eq!(
+            parse_revel_session("REVEL_SESSION=abc; Path=/").unwrap(),
+            "abc"
+        );
+        assert_eq!(parse_revel_session("  abc==  ").unwrap(), "abc==");
+        assert_eq!(parse_revel_session("a=b-c=d").unwrap(), "a=b-c=d");
+        assert_eq!(
+            parse_revel_session(
+                "# Netscape HTTP Cookie File\n\
+                 atcoder.jp\tFALSE\t/\tTRUE\t0\tREVEL_FLASH\t\n\
+                 atcoder.jp\tFALSE\t/\tTRUE\t0\tREVEL_SESSION\tx=y\n"
+            )
+            .unwrap(),
+            "x=y"
+        );
+        assert!(parse_revel_session("# Netscape HTTP Cookie File\n").is_err());
+        assert!(parse_revel_session(" \n").is_err());
+        assert!(parse_revel_session("REVEL_SESSION=").is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::{anyhow, bail, Context as _};
 use cargo_metadata::{Metadata, MetadataCommand, Package, Resolve, Target};
-use std::{env, path::Path, process::Command, str};
+use serde::Deserialize;
+use std::{collections::BTreeMap, env, path::Path, process::Command, str};
 use url::Url;
 
 pub(crate) fn cargo_metadata(manifest_path: Option<&Path>, cwd: &Path) -> anyhow::Result<Metadata> {
@@ -74,6 +75,8 @@ impl MetadataExt for Metadata {
 pub(crate) trait PackageExt {
     fn all_bins(&self) -> Vec<&Target>;
     fn find_bin<'a>(&'a self, name: &str) -> anyhow::Result<&'a Target>;
+    fn contest_id(&self) -> anyhow::Result<String>;
+    fn task_screen_name(&self, bin_name: &str) -> anyhow::Result<Option<String>>;
 }
 
 impl PackageExt for Package {
@@ -86,6 +89,57 @@ impl PackageExt for Package {
             .find(|t| t.name == name)
             .with_context(|| format!("no bin target named `{}`", name))
     }
+
+    fn contest_id(&self) -> anyhow::Result<String> {
+        Ok(atcoder_metadata(self)?
+            .contest
+            .unwrap_or_else(|| self.name.clone()))
+    }
+
+    fn task_screen_name(&self, bin_name: &str) -> anyhow::Result<Option<String>> {
+        Ok(atcoder_metadata(self)?
+            .bin
+            .remove(bin_name)
+            .and_then(|bin| bin.task))
+    }
+}
+
+/// `[package.metadata.cargo-atcoder]`
+///
+/// ```toml
+/// [package.metadata.cargo-atcoder]
+/// contest = "abc001"
+///
+/// [package.metadata.cargo-atcoder.bin.a]
+/// task = "abc001_1"
+/// ```
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AtCoderMetadata {
+    /// Defaults to the package name.
+    contest: Option<String>,
+    #[serde(default)]
+    bin: BTreeMap<String, BinMetadata>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BinMetadata {
+    /// The task screen name, such as `abc001_1`. Defaults to the task whose
+    /// ID (A, B, ...) is the bin name.
+    task: Option<String>,
+}
+
+fn atcoder_metadata(package: &Package) -> anyhow::Result<AtCoderMetadata> {
+    match package.metadata.get("cargo-atcoder") {
+        Some(value) => AtCoderMetadata::deserialize(value).with_context(|| {
+            format!(
+                "invalid `package.metadata.cargo-atcoder` in {}",
+                package.manifest_path,
+            )
+        }),
+        None => Ok(AtCoderMetadata::default()),
+    }
 }
 
 fn all_members(metadata: &Metadata) -> impl Iterator<Item = &Package> {
@@ -0,0 +1,94 @@
+//! Named profiles. Each profile has its own session file and may override
+//! the config with a `[profiles.<name>]` table.
+
+use std::{collections::BTreeSet, fs, path::PathBuf};
+
+use anyhow::{ensure, Result};
+use once_cell::sync::OnceCell;
+
+use crate::{atcoder::AtCoder, cache_dir, config};
+
+pub const DEFAULT: &str = "default";
+
+static CURRENT: OnceCell<Option<String>> = OnceCell::new();
+
+/// Selects the profile for this process. `None` or `"default"` means the default profile.
+pub fn init(name: Option<String>) -> Result<()> {
+    let name = name.filter(|name| name != DEFAULT);
+    if let Some(name) = &name {
+        ensure!(
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "Invalid profile name `{}`: use only alphanumerics, `-` and `_`",
+            name
+        );
+    }
+    let _ = CURRENT.set(name);
+    Ok(())
+}
+
+pub fn current() -> Option<&'static str> {
+    CURRENT.get().and_then(Option::as_deref)
+}
+
+pub fn session_file_for(profile: Option<&str>) -> Result<PathBuf> {
+    Ok(match profile {
+        None => cache_dir()?.join("session.txt"),
+        Some(name) => {
+            let dir = cache_dir()?.join("profiles");
+            fs::create_dir_all(&dir)?;
+            dir.join(name).with_extension("txt")
+        }
+    })
+}
+
+/// Profiles that have a session file or a config table.
+fn names() -> Result<BTreeSet<String>> {
+    let mut names = config::profile_names()?;
+
+    let dir = cache_dir()?.join("profiles");
+    if dir.is_dir() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension() == Some("txt".as_ref()) {
+                if let Some(stem) = path.file_stem() {
+                    names.insert(stem.to_string_lossy().into_owned());
+                }
+            }
+        }
+    }
+    if let Some(name) = current() {
+        names.insert(name.to_owned());
+    }
+    Ok(names)
+}
+
+pub async fn profiles() -> Result<()> {
+    let profiles = Some(None)
+        .into_iter()
+        .chain(names()?.into_iter().map(Some))
+        .collect::<Vec<_>>();
+
+    for profile in &profiles {
+        let path = session_file_for(profile.as_deref())?;
+        let username = if path.exists() {
+            AtCoder::new(&path)?.username().await?
+        } else {
+            None
+        };
+
+        println!(
+            "{} {:16} | {}",
+            if profile.as_deref() == current() {
+                "*"
+            } else {
+                " "
+            },
+            profile.as_deref().unwrap_or(DEFAULT),
+            username.as_deref().unwrap_or("(not logged in)"),
+        );
+    }
+    Ok(())
+}
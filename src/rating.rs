@@ -0,0 +1,249 @@
+//! Estimation of performances and rating changes.
+//!
+//! This follows the formulas in AtCoder's rating system documentation:
+//! <https://img.atcoder.jp/file/AtCoder_rating_system_v2.pdf>
+
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result};
+use console::Style;
+use structopt::StructOpt;
+
+use crate::{
+    atcoder::{AtCoder, Standings, StandingsRow},
+    metadata::{self, MetadataExt as _, PackageExt as _},
+    session_file,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ContestParams {
+    /// APerf of participants who have never been rated.
+    pub default_aperf: f64,
+    /// Upper bound of performances (rated range + 401).
+    pub perf_cap: f64,
+}
+
+impl ContestParams {
+    pub fn for_contest(contest_id: &str) -> Self {
+        let (default_aperf, perf_cap) = if contest_id.starts_with("abc") {
+            (800.0, 2400.0)
+        } else if contest_id.starts_with("arc") {
+            (1000.0, 3200.0)
+        } else {
+            (1200.0, f64::INFINITY)
+        };
+        Self {
+            default_aperf,
+            perf_cap,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Estimate {
+    pub performance: i64,
+    pub old_rating: i64,
+    pub new_rating: i64,
+}
+
+/// Reads APerfs from a JSON file of the form `{"<user>": <aperf>, ...}`.
+pub fn load_aperfs(path: &Path) -> Result<BTreeMap<String, f64>> {
+    let s =
+        fs::read_to_string(path).with_context(|| format!("Failed to read `{}`", path.display()))?;
+    serde_json::from_str(&s).with_context(|| format!("Failed to parse `{}`", path.display()))
+}
+
+/// Estimates the performance and the new rating of every rated participant
+/// who submitted at least once.
+pub fn estimate(
+    standings: &Standings,
+    aperfs: &BTreeMap<String, f64>,
+    params: ContestParams,
+) -> BTreeMap<String, Estimate> {
+    let participants = standings
+        .standings_data
+        .iter()
+        .filter(|row| row.is_rated && row.total_result.count > 0)
+        .collect::<Vec<_>>();
+
+    let aperf_list = participants
+        .iter()
+        .map(|row| match aperfs.get(&row.user_screen_name) {
+            Some(&aperf) => aperf,
+            None if row.competitions == 0 => params.default_aperf,
+            None => inner_rating(row.old_rating as f64, row.competitions),
+        })
+        .collect::<Vec<_>>();
+
+    let mut ret = BTreeMap::new();
+    let mut i = 0;
+    while i < participants.len() {
+        // Participants with the same rank share the averaged rank.
+        let mut j = i;
+        while j < participants.len() && participants[j].rank == participants[i].rank {
+            j += 1;
+        }
+        let rank = (i + 1 + j) as f64 / 2.0;
+        let raw_perf = raw_performance(&aperf_list, rank).min(params.perf_cap);
+
+        for row in &participants[i..j] {
+            ret.insert(
+                row.user_screen_name.clone(),
+                Estimate {
+                    performance: positivize(raw_perf).round() as i64,
+                    old_rating: row.old_rating,
+                    new_rating: new_rating(row, raw_perf),
+                },
+            );
+        }
+        i = j;
+    }
+    ret
+}
+
+/// Solves `Σ 1 / (1 + 6^((X - aperf_i) / 400)) = rank - 0.5` for `X`.
+fn raw_performance(aperfs: &[f64], rank: f64) -> f64 {
+    let expected_rank = |x: f64| -> f64 {
+        aperfs
+            .iter()
+            .map(|a| 1.0 / (1.0 + 6f64.powf((x - a) / 400.0)))
+            .sum()
+    };
+
+    let (mut lo, mut hi) = (-10000.0, 10000.0);
+    for _ in 0..100 {
+        let mid = (lo + hi) / 2.0;
+        if expected_rank(mid) > rank - 0.5 {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+/// Maps values not greater than 400 into `(0, 400]`.
+fn positivize(r: f64) -> f64 {
+    if r <= 400.0 {
+        400.0 / ((400.0 - r) / 400.0).exp()
+    } else {
+        r
+    }
+}
+
+fn unpositivize(r: f64) -> f64 {
+    if r <= 400.0 {
+        400.0 - 400.0 * (400.0 / r.max(1.0)).ln()
+    } else {
+        r
+    }
+}
+
+/// Correction for participants with few contests.
+fn f(n: usize) -> f64 {
+    let n = n as i32;
+    ((1.0 - 0.81f64.powi(n)).sqrt() / (1.0 - 0.9f64.powi(n)) - 1.0) / (19f64.sqrt() - 1.0) * 1200.0
+}
+
+/// Recovers the uncorrected rating from the displayed one.
+fn inner_rating(rating: f64, competitions: usize) -> f64 {
+    if competitions == 0 {
+        return 0.0;
+    }
+    unpositivize(rating) + f(competitions)
+}
+
+/// The rating is `800 * log2(Σ 2^(P_i / 800) 0.9^i / Σ 0.9^i) - f(n)`, so the
+/// weighted sums can be recovered from the current rating and updated with
+/// the new performance.
+fn new_rating(row: &StandingsRow, perf: f64) -> i64 {
+    let n = row.competitions;
+    let old_weight = (1..=n as i32).map(|i| 0.9f64.powi(i)).sum::<f64>();
+    let old_sum = if n == 0 {
+        0.0
+    } else {
+        old_weight * 2f64.powf(inner_rating(row.old_rating as f64, n) / 800.0)
+    };
+
+    let weight = 0.9 * (old_weight + 1.0);
+    let sum = 0.9 * (old_sum + 2f64.powf(perf / 800.0));
+    positivize(800.0 * (sum / weight).log2() - f(n + 1)).round() as i64
+}
+
+#[derive(StructOpt)]
+pub struct PerfOpt {
+    /// Show only these users (comma separated)
+    #[structopt(short, long, value_name("USERS"), use_delimiter(true))]
+    users: Vec<String>,
+    /// JSON file of participants' APerfs (`{"<user>": <aperf>, ...}`)
+    #[structopt(short, long, value_name("PATH"))]
+    ratings: Option<PathBuf>,
+    /// Read standings from a JSON file instead of fetching them
+    #[structopt(long, value_name("PATH"))]
+    standings: Option<PathBuf>,
+    /// [cargo] Package
+    #[structopt(short, long, value_name("SPEC"))]
+    package: Option<String>,
+    /// [cargo] Path to Cargo.toml
+    #[structopt(long, value_name("PATH"))]
+    manifest_path: Option<PathBuf>,
+}
+
+pub async fn perf(opt: PerfOpt) -> Result<()> {
+    let cwd = env::current_dir().with_context(|| "failed to get CWD")?;
+    let metadata = metadata::cargo_metadata(opt.manifest_path.as_deref(), &cwd)?;
+    let contest_id = &metadata
+        .query_for_member(opt.package.as_deref())?
+        .contest_id()?;
+
+    let standings: Standings = if let Some(path) = &opt.standings {
+        let s = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read `{}`", path.display()))?;
+        serde_json::from_str(&s).with_context(|| format!("Failed to parse `{}`", path.display()))?
+    } else {
+        AtCoder::new(&session_file()?)?
+            .standings(contest_id)
+            .await?
+    };
+
+    let aperfs = match &opt.ratings {
+        Some(path) => load_aperfs(path)?,
+        None => BTreeMap::new(),
+    };
+    let estimates = estimate(&standings, &aperfs, ContestParams::for_contest(contest_id));
+
+    let bold = Style::new().bold();
+    println!(
+        "{}",
+        bold.apply_to(format!(
+            "{:>5} | {:20} | {:>5} | {:>6} | {:>6} | {:>5}",
+            "Rank", "User", "Perf", "Old", "New", "Diff"
+        ))
+    );
+    for row in &standings.standings_data {
+        if !opt.users.is_empty()
+            && !opt
+                .users
+                .iter()
+                .any(|u| u.eq_ignore_ascii_case(&row.user_screen_name))
+        {
+            continue;
+        }
+        if let Some(e) = estimates.get(&row.user_screen_name) {
+            println!(
+                "{:>5} | {:20} | {:>5} | {:>6} | {:>6} | {:>+5}",
+                row.rank,
+                row.user_screen_name,
+                e.performance,
+                e.old_rating,
+                e.new_rating,
+                e.new_rating - e.old_rating,
+            );
+        }
+    }
+    Ok(())
+}
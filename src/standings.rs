@@ -0,0 +1,186 @@
+use std::{collections::BTreeMap, env, path::PathBuf, time::Duration};
+
+use anyhow::{Context as _, Result};
+use console::{Style, Term};
+use structopt::StructOpt;
+use tokio::time::sleep;
+use unicode_width::UnicodeWidthStr as _;
+
+use crate::{
+    atcoder::{AtCoder, Standings, StandingsRow, TaskResult},
+    config::read_config,
+    metadata::{self, MetadataExt as _, PackageExt as _},
+    rating::{self, ContestParams, Estimate},
+    session_file, update_interval,
+};
+
+#[derive(StructOpt)]
+pub struct StandingsOpt {
+    /// Show only these users (comma separated)
+    #[structopt(short, long, value_name("USERS"), use_delimiter(true))]
+    users: Vec<String>,
+    /// Number of top users to show when `--users` is not given
+    #[structopt(short = "n", long, default_value = "20")]
+    top: usize,
+    /// Refresh periodically
+    #[structopt(short, long)]
+    watch: bool,
+    /// JSON file of participants' APerfs used for performance estimation
+    #[structopt(short, long, value_name("PATH"))]
+    ratings: Option<PathBuf>,
+    /// [cargo] Package
+    #[structopt(short, long, value_name("SPEC"))]
+    package: Option<String>,
+    /// [cargo] Path to Cargo.toml
+    #[structopt(long, value_name("PATH"))]
+    manifest_path: Option<PathBuf>,
+}
+
+pub async fn standings(opt: StandingsOpt) -> Result<()> {
+    let cwd = env::current_dir().with_context(|| "failed to get CWD")?;
+    let metadata = metadata::cargo_metadata(opt.manifest_path.as_deref(), &cwd)?;
+    let contest_id = &metadata
+        .query_for_member(opt.package.as_deref())?
+        .contest_id()?;
+    let atc = AtCoder::new(&session_file()?)?;
+    let config = read_config()?;
+    let aperfs = match &opt.ratings {
+        Some(path) => rating::load_aperfs(path)?,
+        None => BTreeMap::new(),
+    };
+
+    let term = Term::stdout();
+    loop {
+        let standings = atc.standings(contest_id).await?;
+
+        if opt.watch {
+            term.clear_screen()?;
+        }
+        let estimates =
+            rating::estimate(&standings, &aperfs, ContestParams::for_contest(contest_id));
+        print_standings(&standings, &select_rows(&standings, &opt), &estimates);
+
+        if !opt.watch {
+            break;
+        }
+        sleep(Duration::from_millis(update_interval(&config))).await;
+    }
+    Ok(())
+}
+
+fn select_rows<'a>(standings: &'a Standings, opt: &StandingsOpt) -> Vec<&'a StandingsRow> {
+    if opt.users.is_empty() {
+        standings.standings_data.iter().take(opt.top).collect()
+    } else {
+        standings
+            .standings_data
+            .iter()
+            .filter(|row| {
+                opt.users
+                    .iter()
+                    .any(|u| u.eq_ignore_ascii_case(&row.user_screen_name))
+            })
+            .collect()
+    }
+}
+
+fn print_standings(
+    standings: &Standings,
+    rows: &[&StandingsRow],
+    estimates: &BTreeMap<String, Estimate>,
+) {
+    let bold = Style::new().bold();
+    let green = Style::new().green();
+    let red = Style::new().red();
+    let yellow = Style::new().yellow();
+
+    let user_width = rows
+        .iter()
+        .map(|r| r.user_screen_name.width())
+        .chain(Some("User".len()))
+        .max()
+        .unwrap();
+    const CELL_WIDTH: usize = 12;
+
+    let mut header = format!(
+        "{:>5} | {:user_width$} | {:>7} | {:>7} | {:>9} | {:>5} | {:>11} |",
+        "Rank",
+        "User",
+        "Score",
+        "Penalty",
+        "Time",
+        "Perf",
+        "Rating",
+        user_width = user_width,
+    );
+    for task in &standings.task_info {
+        header += &format!(" {:^width$} |", task.assignment, width = CELL_WIDTH);
+    }
+    println!("{}", bold.apply_to(header));
+
+    for row in rows {
+        let total = &row.total_result;
+        let (perf, rating) = match estimates.get(&row.user_screen_name) {
+            Some(e) => (
+                e.performance.to_string(),
+                format!("{} ({:+})", e.new_rating, e.new_rating - e.old_rating),
+            ),
+            None => ("-".to_owned(), "-".to_owned()),
+        };
+        print!(
+            "{:>5} | {:user_width$} | {:>7} | {:>7} | {:>9} | {:>5} | {:>11} |",
+            row.rank,
+            row.user_screen_name,
+            format_score(total.score),
+            total.penalty,
+            format_elapsed(total.elapsed),
+            perf,
+            rating,
+            user_width = user_width,
+        );
+        for task in &standings.task_info {
+            let cell = row.task_results.get(&task.task_screen_name);
+            let text = format!("{:^width$}", format_task_result(cell), width = CELL_WIDTH);
+            let text = match cell {
+                Some(r) if r.pending => yellow.apply_to(text),
+                Some(r) if r.score > 0 => green.apply_to(text),
+                Some(r) if r.failure > 0 => red.apply_to(text),
+                _ => Style::new().apply_to(text),
+            };
+            print!(" {} |", text);
+        }
+        println!();
+    }
+}
+
+fn format_task_result(result: Option<&TaskResult>) -> String {
+    match result {
+        None => "-".to_owned(),
+        Some(r) if r.pending => "?".to_owned(),
+        Some(r) if r.score > 0 || r.count > r.failure => {
+            let mut s = format_score(r.score);
+            if r.penalty > 0 {
+                s += &format!("({})", r.penalty);
+            }
+            format!("{} {}", s, format_elapsed(r.elapsed))
+        }
+        Some(r) => format!("({})", r.failure),
+    }
+}
+
+fn format_score(score: i64) -> String {
+    if score % 100 == 0 {
+        (score / 100).to_string()
+    } else {
+        format!("{:.2}", score as f64 / 100.0)
+    }
+}
+
+fn format_elapsed(nanos: i64) -> String {
+    let secs = nanos / 1_000_000_000;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
@@ -0,0 +1,822 @@
+use anyhow::{anyhow, bail, Result};
+use console::Style;
+use ego_tree::NodeRef;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use scraper::{element_ref::ElementRef, Html, Node};
+use std::{fmt::Write as _, str::FromStr};
+
+use crate::atcoder::{selector, Problem, TestCase};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    Ja,
+    En,
+}
+
+impl Lang {
+    fn class_name(self) -> &'static str {
+        match self {
+            Lang::Ja => "lang-ja",
+            Lang::En => "lang-en",
+        }
+    }
+
+    fn other(self) -> Lang {
+        match self {
+            Lang::Ja => Lang::En,
+            Lang::En => Lang::Ja,
+        }
+    }
+}
+
+impl FromStr for Lang {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match &*s.to_lowercase() {
+            "ja" => Ok(Lang::Ja),
+            "en" => Ok(Lang::En),
+            _ => Err(anyhow!("unknown language `{}` (expected `ja` or `en`)", s)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Statement {
+    pub title: String,
+    pub url: String,
+    pub time_limit: String,
+    pub memory_limit: String,
+    pub sections: Vec<Section>,
+}
+
+#[derive(Debug)]
+pub struct Section {
+    pub heading: String,
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug)]
+pub enum Block {
+    Heading(String),
+    Text(String),
+    Pre(String),
+    List(Vec<String>),
+    Table(Vec<Vec<String>>),
+}
+
+/// Parses `#task-statement` of a task page.
+///
+/// If the requested language is not available, the other one is used. Old
+/// contests which have no `.lang-*` spans are parsed as a whole.
+pub fn parse_sections(doc: &Html, lang: Lang) -> Result<Vec<Section>> {
+    let root = doc
+        .select(&selector("#task-statement"))
+        .next()
+        .ok_or_else(|| anyhow!("Could not find the problem statement"))?;
+
+    let lang_root = [lang, lang.other()].iter().find_map(|lang| {
+        root.select(&selector(&format!(".{}", lang.class_name())))
+            .next()
+    });
+
+    let sections = lang_root
+        .unwrap_or(root)
+        .select(&selector("section"))
+        .filter_map(parse_section)
+        .collect::<Vec<_>>();
+
+    if sections.is_empty() {
+        bail!("Could not find any section in the problem statement");
+    }
+    Ok(sections)
+}
+
+fn parse_section(section: ElementRef) -> Option<Section> {
+    let heading = section
+        .select(&selector("h3"))
+        .next()?
+        .text()
+        .collect::<String>()
+        .trim()
+        .to_owned();
+
+    let mut blocks = parse_blocks(section);
+    if let Some(Block::Heading(_)) = blocks.first() {
+        blocks.remove(0);
+    }
+
+    Some(Section { heading, blocks })
+}
+
+/// Converts the content of an element into text blocks. Only `<var>` is treated as TeX.
+pub fn parse_blocks(elem: ElementRef) -> Vec<Block> {
+    parse_blocks_with(elem, false)
+}
+
+/// Like `parse_blocks`, for an element rendered by KaTeX's auto-render (e.g. an editorial),
+/// whose text may contain math delimited by `\(..\)`, `\[..\]`, `$$..$$` and `$..$`.
+pub fn parse_katex_blocks(elem: ElementRef) -> Vec<Block> {
+    parse_blocks_with(elem, true)
+}
+
+fn parse_blocks_with(elem: ElementRef, katex: bool) -> Vec<Block> {
+    let mut blocks = vec![];
+    let mut inline = String::new();
+    collect_blocks(*elem, katex, &mut blocks, &mut inline);
+    flush_inline(&mut blocks, &mut inline);
+    blocks
+}
+
+fn collect_blocks(node: NodeRef<Node>, katex: bool, blocks: &mut Vec<Block>, inline: &mut String) {
+    for child in node.children() {
+        let elem = match ElementRef::wrap(child) {
+            Some(elem) => elem,
+            None => {
+                render_inline(child, katex, inline);
+                continue;
+            }
+        };
+
+        match elem.value().name() {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                flush_inline(blocks, inline);
+                blocks.push(Block::Heading(normalize_spaces(
+                    &elem.text().collect::<String>(),
+                )));
+            }
+            "script" | "style" => {}
+            "pre" => {
+                flush_inline(blocks, inline);
+                let mut text = String::new();
+                render_pre(*elem, &mut text);
+                blocks.push(Block::Pre(text.trim_end().to_owned()));
+            }
+            "ul" | "ol" => {
+                flush_inline(blocks, inline);
+                let items = elem
+                    .select(&selector("li"))
+                    .map(|li| {
+                        let mut text = String::new();
+                        render_inline(*li, katex, &mut text);
+                        normalize_spaces(&text)
+                    })
+                    .collect();
+                blocks.push(Block::List(items));
+            }
+            "table" => {
+                flush_inline(blocks, inline);
+                let sel_cell = selector("th, td");
+                let rows = elem
+                    .select(&selector("tr"))
+                    .map(|tr| {
+                        tr.select(&sel_cell)
+                            .map(|cell| {
+                                let mut text = String::new();
+                                render_inline(*cell, katex, &mut text);
+                                normalize_spaces(&text)
+                            })
+                            .collect()
+                    })
+                    .collect();
+                blocks.push(Block::Table(rows));
+            }
+            "p" | "div" | "section" | "blockquote" => {
+                flush_inline(blocks, inline);
+                collect_blocks(*elem, katex, blocks, inline);
+                flush_inline(blocks, inline);
+            }
+            _ => render_inline(child, katex, inline),
+        }
+    }
+}
+
+fn flush_inline(blocks: &mut Vec<Block>, inline: &mut String) {
+    let text = normalize_spaces(inline);
+    if !text.is_empty() {
+        blocks.push(Block::Text(text));
+    }
+    inline.clear();
+}
+
+fn render_inline(node: NodeRef<Node>, katex: bool, out: &mut String) {
+    match node.value() {
+        Node::Text(text) if katex => out.push_str(&replace_inline_tex(text)),
+        Node::Text(text) => out.push_str(text),
+        Node::Element(elem) => match elem.name() {
+            "var" => out.push_str(&tex_to_text(
+                &ElementRef::wrap(node).unwrap().text().collect::<String>(),
+            )),
+            "br" => out.push('\n'),
+            "img" => {
+                let _ = write!(out, "[image: {}]", elem.attr("src").unwrap_or_default());
+            }
+            _ => {
+                for child in node.children() {
+                    render_inline(child, katex, out);
+                }
+            }
+        },
+        _ => {}
+    }
+}
+
+fn render_pre(node: NodeRef<Node>, out: &mut String) {
+    for child in node.children() {
+        match child.value() {
+            Node::Text(text) => out.push_str(text),
+            Node::Element(elem) if elem.name() == "var" => {
+                let tex = ElementRef::wrap(child).unwrap().text().collect::<String>();
+                out.push_str(&tex_to_text(&tex));
+            }
+            Node::Element(_) => render_pre(child, out),
+            _ => {}
+        }
+    }
+}
+
+/// Collapses runs of spaces in each line, keeping explicit line breaks.
+fn normalize_spaces(s: &str) -> String {
+    s.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn replace_inline_tex(text: &str) -> String {
+    static INLINE_TEX_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?s)\\\((.*?)\\\)|\\\[(.*?)\\\]|\$\$(.*?)\$\$|\$([^$]+?)\$").unwrap()
+    });
+    INLINE_TEX_RE
+        .replace_all(text, |caps: &regex::Captures| {
+            let tex = (1..=4).find_map(|i| caps.get(i)).map_or("", |m| m.as_str());
+            tex_to_text(tex)
+        })
+        .replace('\n', " ")
+}
+
+/// Converts a KaTeX expression into readable plain text (e.g. `1 \leq N \leq 10^{5}` into `1 ≤ N ≤ 10^5`).
+pub fn tex_to_text(tex: &str) -> String {
+    let chars = tex.chars().collect::<Vec<_>>();
+    let mut pos = 0;
+    let mut out = String::new();
+    render_tex(&chars, &mut pos, &mut out, false);
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn render_tex(cs: &[char], pos: &mut usize, out: &mut String, in_group: bool) {
+    while *pos < cs.len() {
+        let c = cs[*pos];
+        *pos += 1;
+        match c {
+            '}' if in_group => return,
+            '{' => render_tex(cs, pos, out, true),
+            '\\' => {
+                let name = tex_command_name(cs, pos);
+                render_tex_command(&name, cs, pos, out);
+            }
+            '_' | '^' => {
+                let arg = tex_arg(cs, pos);
+                out.push(c);
+                if arg.chars().all(char::is_alphanumeric) {
+                    out.push_str(&arg);
+                } else {
+                    let _ = write!(out, "{{{}}}", arg);
+                }
+            }
+            '~' => out.push(' '),
+            c => out.push(c),
+        }
+    }
+}
+
+fn tex_command_name(cs: &[char], pos: &mut usize) -> String {
+    let start = *pos;
+    while *pos < cs.len() && cs[*pos].is_ascii_alphabetic() {
+        *pos += 1;
+    }
+    if *pos == start && *pos < cs.len() {
+        *pos += 1;
+    }
+    cs[start..*pos].iter().collect()
+}
+
+fn tex_arg(cs: &[char], pos: &mut usize) -> String {
+    while *pos < cs.len() && cs[*pos].is_whitespace() {
+        *pos += 1;
+    }
+    let mut out = String::new();
+    match cs.get(*pos) {
+        Some('{') => {
+            *pos += 1;
+            render_tex(cs, pos, &mut out, true);
+        }
+        Some('\\') => {
+            *pos += 1;
+            let name = tex_command_name(cs, pos);
+            render_tex_command(&name, cs, pos, &mut out);
+        }
+        Some(&c) => {
+            *pos += 1;
+            out.push(c);
+        }
+        None => {}
+    }
+    out.trim().to_owned()
+}
+
+fn render_tex_command(name: &str, cs: &[char], pos: &mut usize, out: &mut String) {
+    let parenthesize = |s: String| {
+        if s.chars().all(char::is_alphanumeric) {
+            s
+        } else {
+            format!("({})", s)
+        }
+    };
+
+    let symbol = match name {
+        "le" | "leq" | "leqq" | "leqslant" => "≤",
+        "ge" | "geq" | "geqq" | "geqslant" => "≥",
+        "ne" | "neq" => "≠",
+        "lt" => "<",
+        "gt" => ">",
+        "times" => "×",
+        "cdot" => "⋅",
+        "div" => "÷",
+        "ldots" | "cdots" | "dots" | "dotsc" | "dotsb" => "…",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        "pm" => "±",
+        "mp" => "∓",
+        "infty" => "∞",
+        "sum" => "Σ",
+        "prod" => "Π",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "supseteq" => "⊇",
+        "cup" => "∪",
+        "cap" => "∩",
+        "setminus" => "∖",
+        "emptyset" | "varnothing" => "∅",
+        "forall" => "∀",
+        "exists" => "∃",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        "lnot" | "neg" => "¬",
+        "oplus" => "⊕",
+        "otimes" => "⊗",
+        "to" | "rightarrow" => "→",
+        "gets" | "leftarrow" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" | "iff" => "⇔",
+        "equiv" => "≡",
+        "approx" => "≈",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "mid" | "vert" | "|" => "|",
+        "Vert" => "‖",
+        "circ" => "∘",
+        "deg" => "°",
+        "prime" => "′",
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" | "varepsilon" => "ε",
+        "theta" => "θ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "pi" => "π",
+        "rho" => "ρ",
+        "sigma" => "σ",
+        "tau" => "τ",
+        "phi" | "varphi" => "φ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Phi" => "Φ",
+        "Omega" => "Ω",
+        "max" | "min" | "log" | "ln" | "gcd" | "lcm" | "sin" | "cos" | "tan" | "exp" | "det"
+        | "lim" | "sup" | "inf" | "arg" => name,
+        "bmod" | "mod" => " mod ",
+        "{" | "}" | "%" | "&" | "#" | "_" | "$" => name,
+        "," | ";" | ":" | " " | "quad" | "qquad" | "\\" => " ",
+        "!" | "left" | "right" | "big" | "Big" | "bigg" | "Bigg" | "bigl" | "bigr" | "Bigl"
+        | "Bigr" | "displaystyle" | "textstyle" | "limits" | "nolimits" | "rm" | "bf" | "it" => "",
+        "pmod" => {
+            let _ = write!(out, " (mod {})", tex_arg(cs, pos));
+            return;
+        }
+        "frac" | "dfrac" | "tfrac" => {
+            let num = parenthesize(tex_arg(cs, pos));
+            let den = parenthesize(tex_arg(cs, pos));
+            let _ = write!(out, "{}/{}", num, den);
+            return;
+        }
+        "sqrt" => {
+            let _ = write!(out, "√{}", parenthesize(tex_arg(cs, pos)));
+            return;
+        }
+        "binom" => {
+            let n = tex_arg(cs, pos);
+            let k = tex_arg(cs, pos);
+            let _ = write!(out, "C({}, {})", n, k);
+            return;
+        }
+        "mathrm" | "mathit" | "mathbf" | "mathtt" | "mathsf" | "mathcal" | "mathbb" | "text"
+        | "textrm" | "textbf" | "textit" | "texttt" | "operatorname" | "overline" | "bar"
+        | "hat" | "tilde" | "vec" | "underline" | "boldsymbol" => {
+            out.push_str(&tex_arg(cs, pos));
+            return;
+        }
+        name => name,
+    };
+    out.push_str(symbol);
+}
+
+pub fn print_statement(statement: &Statement) {
+    let bold = Style::new().bold();
+    let heading = Style::new().cyan().bold();
+
+    println!("{}", bold.apply_to(&statement.title));
+    println!("{}", statement.url);
+    println!(
+        "Time Limit: {} / Memory Limit: {}",
+        statement.time_limit, statement.memory_limit,
+    );
+
+    for section in &statement.sections {
+        println!();
+        println!("{}", heading.apply_to(&section.heading));
+        println!();
+        print_blocks(&section.blocks);
+    }
+}
+
+pub fn print_blocks(blocks: &[Block]) {
+    let heading = Style::new().cyan().bold();
+
+    for (i, block) in blocks.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        match block {
+            Block::Heading(text) => println!("{}", heading.apply_to(text)),
+            Block::Text(text) => println!("{}", text),
+            Block::Pre(text) => {
+                for line in text.lines() {
+                    println!("    {}", line);
+                }
+            }
+            Block::List(items) => {
+                for item in items {
+                    println!("  - {}", item.replace('\n', "\n    "));
+                }
+            }
+            Block::Table(rows) => {
+                for row in rows {
+                    println!("  | {} |", row.join(" | "));
+                }
+            }
+        }
+    }
+}
+
+pub fn to_markdown(statement: &Statement) -> String {
+    let mut md = String::new();
+
+    let _ = writeln!(md, "# [{}]({})", statement.title, statement.url);
+    let _ = writeln!(md);
+    let _ = writeln!(
+        md,
+        "Time Limit: {} / Memory Limit: {}",
+        statement.time_limit, statement.memory_limit,
+    );
+
+    for section in &statement.sections {
+        let _ = writeln!(md);
+        let _ = writeln!(md, "## {}", section.heading);
+        write_markdown_blocks(&mut md, &section.blocks);
+    }
+
+    md
+}
+
+pub fn write_markdown_blocks(md: &mut String, blocks: &[Block]) {
+    for block in blocks {
+        let _ = writeln!(md);
+        match block {
+            Block::Heading(text) => {
+                let _ = writeln!(md, "### {}", text);
+            }
+            Block::Text(text) => {
+                let _ = writeln!(md, "{}", text.replace('\n', "  \n"));
+            }
+            Block::Pre(text) => {
+                let _ = writeln!(md, "```\n{}\n```", text);
+            }
+            Block::List(items) => {
+                for item in items {
+                    let _ = writeln!(md, "- {}", item.replace('\n', "  \n  "));
+                }
+            }
+            Block::Table(rows) => {
+                for (i, row) in rows.iter().enumerate() {
+                    let _ = writeln!(md, "| {} |", row.join(" | "));
+                    if i == 0 {
+                        let _ = writeln!(md, "|{}", " --- |".repeat(row.len()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+const SAMPLE_INPUT_LABELS: [&str; 2] = ["入力例", "Sample Input"];
+const SAMPLE_OUTPUT_LABELS: [&str; 2] = ["出力例", "Sample Output"];
+
+/// The title, URL, limits, statement and samples of a problem as `//!` lines.
+///
+/// The samples are written in the form [`read_doc_comment_samples`] reads.
+/// Sections of the statement for the samples are replaced with them.
+pub fn to_doc_comment(
+    problem: &Problem,
+    statement: Option<&Statement>,
+    test_cases: &[TestCase],
+    lang: Lang,
+) -> String {
+    let mut md = String::new();
+
+    let _ = writeln!(md, "# {} - {}", problem.id, problem.name);
+    let _ = writeln!(md);
+    let _ = writeln!(md, "<{}>", problem.full_url());
+    let _ = writeln!(md);
+    let _ = writeln!(
+        md,
+        "Time Limit: {} / Memory Limit: {}",
+        problem.tle, problem.mle,
+    );
+
+    let is_sample = |heading: &str| {
+        SAMPLE_INPUT_LABELS
+            .iter()
+            .chain(&SAMPLE_OUTPUT_LABELS)
+            .any(|label| heading.starts_with(label))
+    };
+    for section in statement.iter().flat_map(|s| &s.sections) {
+        if is_sample(&section.heading) {
+            continue;
+        }
+        let _ = writeln!(md);
+        let _ = writeln!(md, "## {}", section.heading);
+        write_markdown_blocks(&mut md, &section.blocks);
+    }
+
+    let (input_label, output_label) = match lang {
+        Lang::Ja => (SAMPLE_INPUT_LABELS[0], SAMPLE_OUTPUT_LABELS[0]),
+        Lang::En => (SAMPLE_INPUT_LABELS[1], SAMPLE_OUTPUT_LABELS[1]),
+    };
+    for (i, tc) in test_cases.iter().enumerate() {
+        for (label, text) in &[(input_label, &tc.input), (output_label, &tc.output)] {
+            let _ = writeln!(md);
+            let _ = writeln!(md, "## {} {}", label, i + 1);
+            let _ = writeln!(md);
+            let _ = writeln!(md, "```text\n{}\n```", text);
+        }
+    }
+
+    md.lines()
+        .map(|line| {
+            if line.is_empty() {
+                "//!\n".to_owned()
+            } else {
+                format!("//! {}\n", line)
+            }
+        })
+        .collect()
+}
+
+/// Normalizes the text of a sample, whether scraped or read from a doc comment, so that
+/// both have `\n` line endings and no trailing newlines.
+pub fn sample_text(text: &str) -> String {
+    text.lines()
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end_matches('\n')
+        .to_owned()
+}
+
+/// Reads the samples written by [`to_doc_comment`] from the leading `//!` lines
+/// of `source`.
+pub fn read_doc_comment_samples(source: &str) -> Option<Vec<TestCase>> {
+    let lines = source
+        .lines()
+        .map_while(|line| line.strip_prefix("//!"))
+        .map(|line| line.strip_prefix(' ').unwrap_or(line));
+
+    let (mut inputs, mut outputs) = (vec![], vec![]);
+    // The samples which the next code block is pushed to.
+    let mut dest = None;
+    let mut block: Option<Vec<&str>> = None;
+    for line in lines {
+        if let Some(block_lines) = &mut block {
+            if line.trim_end() == "```" {
+                if let Some(dest) = dest.take() {
+                    Vec::push(dest, sample_text(&block_lines.join("\n")));
+                }
+                block = None;
+            } else {
+                block_lines.push(line);
+            }
+        } else if let Some(heading) = line.strip_prefix("## ") {
+            dest = if SAMPLE_INPUT_LABELS.iter().any(|l| heading.starts_with(l)) {
+                Some(&mut inputs)
+            } else if SAMPLE_OUTPUT_LABELS.iter().any(|l| heading.starts_with(l)) {
+                Some(&mut outputs)
+            } else {
+                None
+            };
+        } else if line.starts_with("```") {
+            block = Some(vec![]);
+        }
+    }
+
+    if inputs.is_empty() || inputs.len() != outputs.len() {
+        return None;
+    }
+    Some(
+        inputs
+            .into_iter()
+            .zip(outputs)
+            .map(|(input, output)| TestCase { input, output })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tex_to_text_symbols_and_scripts() {
+        assert_eq!(tex_to_text(r"1 \leq N \leq 10^{5}"), "1 ≤ N ≤ 10^5");
+        assert_eq!(tex_to_text(r"A_1, A_2, \ldots, A_N"), "A_1, A_2, …, A_N");
+        assert_eq!(tex_to_text(r"A_{i+1} \neq A_i"), "A_{i+1} ≠ A_i");
+        assert_eq!(tex_to_text(r"2^{N-1} \times 3"), "2^{N-1} × 3");
+        assert_eq!(tex_to_text(r"\{1, 2, \dots, N\}"), "{1, 2, …, N}");
+    }
+
+    #[test]
+    fn tex_to_text_commands_with_arguments() {
+        assert_eq!(tex_to_text(r"\frac{N(N-1)}{2}"), "(N(N-1))/2");
+        assert_eq!(tex_to_text(r"\dfrac{a}{b}"), "a/b");
+        assert_eq!(tex_to_text(r"\sqrt{N}"), "√N");
+        assert_eq!(tex_to_text(r"\sqrt{x+y}"), "√(x+y)");
+        assert_eq!(tex_to_text(r"\binom{N}{K}"), "C(N, K)");
+        assert_eq!(tex_to_text(r"x \pmod{998244353}"), "x (mod 998244353)");
+        assert_eq!(tex_to_text(r"a \bmod m"), "a mod m");
+        assert_eq!(tex_to_text(r"\mathrm{YES}"), "YES");
+        assert_eq!(tex_to_text(r"\max(A_i, B_i)"), "max(A_i, B_i)");
+    }
+
+    #[test]
+    fn tex_to_text_unknown_commands_and_spacing() {
+        assert_eq!(tex_to_text(r"\unknown"), "unknown");
+        assert_eq!(tex_to_text(r"1\,000"), "1 000");
+        assert_eq!(tex_to_text(r"\left( x \right)"), "( x )");
+        assert_eq!(tex_to_text("  N \n M  "), "N M");
+    }
+
+    static STATEMENT: &str = r#"
+<div id="task-statement">
+<span class="lang">
+<span class="lang-ja">
+<div class="part"><section>
+<h3>問題文</h3>
+<p><var>N</var> 個の整数 <var>A_1, \ldots, A_N</var> が与えられます。</p>
+</section></div>
+<div class="part"><section>
+<h3>制約</h3>
+<ul><li><var>1 \leq N \leq 10^5</var></li><li>入力はすべて整数</li></ul>
+</section></div>
+<div class="io-style"><div class="part"><section>
+<h3>入力</h3>
+<p>入力は以下の形式で標準入力から与えられる。</p>
+<pre><var>N</var>
+<var>A_1</var> <var>\ldots</var> <var>A_N</var>
+</pre>
+</section></div></div>
+</span>
+<span class="lang-en">
+<div class="part"><section>
+<h3>Problem Statement</h3>
+<p>You are given <var>N</var> integers.</p>
+</section></div>
+</span>
+</span>
+</div>
+"#;
+
+    #[test]
+    fn parse_sections_in_the_requested_language() {
+        let doc = Html::parse_document(STATEMENT);
+
+        let sections = parse_sections(&doc, Lang::Ja).unwrap();
+        let headings = sections.iter().map(|s| &*s.heading).collect::<Vec<_>>();
+        assert_eq!(headings, ["問題文", "制約", "入力"]);
+        match &*sections[0].blocks {
+            [Block::Text(text)] => assert_eq!(text, "N 個の整数 A_1, …, A_N が与えられます。"),
+            blocks => panic!("unexpected blocks: {:?}", blocks),
+        }
+        match &*sections[1].blocks {
+            [Block::List(items)] => assert_eq!(items, &["1 ≤ N ≤ 10^5", "入力はすべて整数"]),
+            blocks => panic!("unexpected blocks: {:?}", blocks),
+        }
+        match &*sections[2].blocks {
+            [Block::Text(_), Block::Pre(pre)] => assert_eq!(pre, "N\nA_1 … A_N"),
+            blocks => panic!("unexpected blocks: {:?}", blocks),
+        }
+
+        let sections = parse_sections(&doc, Lang::En).unwrap();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].heading, "Problem Statement");
+    }
+
+    #[test]
+    fn parse_sections_falls_back_to_the_other_language() {
+        let doc = Html::parse_document(
+            r#"<div id="task-statement"><span class="lang"><span class="lang-ja">
+            <section><h3>問題文</h3><p>日本語のみ</p></section>
+            </span></span></div>"#,
+        );
+        let sections = parse_sections(&doc, Lang::En).unwrap();
+        assert_eq!(sections[0].heading, "問題文");
+    }
+
+    #[test]
+    fn parse_sections_without_language_spans() {
+        let doc = Html::parse_document(
+            r#"<div id="task-statement">
+            <div class="part"><section><h3>問題文</h3><p>古い形式</p></section></div>
+            <div class="part"><section><h3>入力例 1</h3><pre>1
+</pre></section></div>
+            </div>"#,
+        );
+        let sections = parse_sections(&doc, Lang::Ja).unwrap();
+        let headings = sections.iter().map(|s| &*s.heading).collect::<Vec<_>>();
+        assert_eq!(headings, ["問題文", "入力例 1"]);
+    }
+
+    #[test]
+    fn parse_sections_requires_the_statement() {
+        let doc = Html::parse_document("<div id=\"main-container\"></div>");
+        assert!(parse_sections(&doc, Lang::Ja).is_err());
+        let doc = Html::parse_document("<div id=\"task-statement\"><p>no sections</p></div>");
+        assert!(parse_sections(&doc, Lang::Ja).is_err());
+    }
+
+    #[test]
+    fn doc_comment_samples_keep_leading_spaces() {
+        let source = "//! ## 入力例 1\n//!\n//! ```text\n//!   1 2\n//! 3\n//!\n//! ```\n//!\n\
+                      //! ## 出力例 1\n//!\n//! ```text\n//! 6\n//! ```\nfn main() {}\n";
+        let samples = read_doc_comment_samples(source).unwrap();
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].input, "  1 2\n3");
+        assert_eq!(samples[0].output, "6");
+    }
+
+    #[test]
+    fn dollars_are_tex_only_in_katex_elements() {
+        let doc = Html::parse_fragment(r#"<div><p>$100 for <var>N \leq 3</var> items</p></div>"#);
+        let div = doc.select(&selector("div")).next().unwrap();
+        match &*parse_blocks(div) {
+            [Block::Text(text)] => assert_eq!(text, "$100 for N ≤ 3 items"),
+            blocks => panic!("unexpected blocks: {:?}", blocks),
+        }
+
+        let doc = Html::parse_fragment(r#"<div><p>$N \leq 3$ and \(a_i \neq b\)</p></div>"#);
+        let div = doc.select(&selector("div")).next().unwrap();
+        match &*parse_katex_blocks(div) {
+            [Block::Text(text)] => assert_eq!(text, "N ≤ 3 and a_i ≠ b"),
+            blocks => panic!("unexpected blocks: {:?}", blocks),
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Templates of the files of new projects.
+//!
+//! A template directory looks like this:
+//!
+//! ```text
+//! templates/
+//! ├── main.rs            # the source of each bin
+//! ├── files/             # extra files put into each package
+//! └── ahc/               # for contests whose ID starts with `ahc`
+//!     ├── main.rs
+//!     └── files/
+//!         └── src/bin/gen.rs
+//! ```
+//!
+//! `{{ name }}` in templates is replaced with the variable `name`. Unknown names
+//! are left as they are.
+
+use anyhow::{Context as _, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::atcoder::Problem;
+use crate::config::{self, Project};
+
+pub type Vars = BTreeMap<&'static str, String>;
+
+pub struct Templates<'a> {
+    project: &'a Project,
+    dir: Option<PathBuf>,
+}
+
+impl<'a> Templates<'a> {
+    /// `dir` takes precedence over `project.template_dir`.
+    pub fn new(project: &'a Project, dir: Option<PathBuf>) -> Result<Self> {
+        let dir = match dir {
+            Some(dir) => Some(dir),
+            None => project
+                .template_dir
+                .as_deref()
+                .map(config::resolve_path)
+                .transpose()?,
+        };
+        Ok(Self { project, dir })
+    }
+
+    /// The template of the bin for `problem_id`, which is the first of:
+    ///
+    /// 1. the first of `project.template_overrides` matching `<contest_id>/<problem_id>`
+    /// 2. `<dir>/<contest type>/main.rs`
+    /// 3. `<dir>/main.rs`
+    /// 4. `project.template`
+    pub fn bin(&self, contest_id: &str, problem_id: &str) -> Result<String> {
+        let name = format!("{}/{}", contest_id, problem_id);
+        if let Some(o) = self
+            .project
+            .template_overrides
+            .iter()
+            .find(|o| matches(&o.pattern, &name))
+        {
+            let path = match &self.dir {
+                Some(dir) => dir.join(&o.template),
+                None => config::resolve_path(&o.template)?,
+            };
+            return read(&path);
+        }
+
+        if let Some(dir) = &self.dir {
+            for path in &[
+                dir.join(contest_type(contest_id)).join("main.rs"),
+                dir.join("main.rs"),
+            ] {
+                if path.is_file() {
+                    return read(path);
+                }
+            }
+        }
+        Ok(self.project.template.clone())
+    }
+
+    /// Extra files in `<dir>/files` and `<dir>/<contest type>/files`, keyed by
+    /// the paths relative to the package. The latter overrides the former.
+    pub fn extra_files(&self, contest_id: &str) -> Result<BTreeMap<PathBuf, String>> {
+        let mut files = BTreeMap::new();
+        if let Some(dir) = &self.dir {
+            for files_dir in &[
+                dir.join("files"),
+                dir.join(contest_type(contest_id)).join("files"),
+            ] {
+                if files_dir.is_dir() {
+                    collect_files(files_dir, Path::new(""), &mut files)?;
+                }
+            }
+        }
+        Ok(files)
+    }
+}
+
+fn collect_files(root: &Path, rel: &Path, files: &mut BTreeMap<PathBuf, String>) -> Result<()> {
+    let dir = root.join(rel);
+    let entries =
+        fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        let rel = rel.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            collect_files(root, &rel, files)?;
+        } else {
+            files.insert(rel.clone(), read(&root.join(&rel))?);
+        }
+    }
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// The leading alphabets of the contest ID, such as `abc` for `abc123`.
+pub fn contest_type(contest_id: &str) -> String {
+    contest_id
+        .chars()
+        .take_while(char::is_ascii_alphabetic)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Matches `s` against `pattern`, where `*` matches any string.
+fn matches(pattern: &str, s: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match s.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts = parts.collect::<Vec<_>>();
+    match parts.split_last() {
+        None => rest.is_empty(),
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(i) => rest = &rest[i + part.len()..],
+                    None => return false,
+                }
+            }
+            rest.ends_with(last)
+        }
+    }
+}
+
+/// Variables for the bin of `problem_id`, or for the extra files if it is `None`.
+/// The ones that are not known are empty.
+pub fn vars(
+    contest_id: &str,
+    problem_id: Option<&str>,
+    problem: Option<&Problem>,
+    sample_count: Option<usize>,
+) -> Vars {
+    let mut vars = Vars::new();
+    vars.insert("contest_id", contest_id.to_owned());
+    vars.insert("contest_type", contest_type(contest_id));
+    vars.insert("problem_id", problem_id.unwrap_or_default().to_owned());
+    vars.insert(
+        "task_id",
+        problem
+            .and_then(|p| p.url.rsplit('/').next())
+            .unwrap_or_default()
+            .to_owned(),
+    );
+    vars.insert("title", problem.map(|p| p.name.clone()).unwrap_or_default());
+    vars.insert("url", problem.map(Problem::full_url).unwrap_or_default());
+    vars.insert(
+        "time_limit",
+        problem.map(|p| p.tle.clone()).unwrap_or_default(),
+    );
+    vars.insert(
+        "memory_limit",
+        problem.map(|p| p.mle.clone()).unwrap_or_default(),
+    );
+    vars.insert(
+        "sample_count",
+        sample_count.map(|n| n.to_string()).unwrap_or_default(),
+    );
+    vars
+}
+
+static VAR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{\s*([a-z_]+)\s*\}\}").unwrap());
+
+pub fn render(template: &str, vars: &Vars) -> String {
+    VAR_RE
+        .replace_all(template, |caps: &Captures| {
+            vars.get(&caps[1])
+                .cloned()
+                .unwrap_or_else(|| caps[0].to_owned())
+        })
+        .into_owned()
+}
+
+/// Whether `template` refers to the variable `name`.
+pub fn uses(template: &str, name: &str) -> bool {
+    VAR_RE.captures_iter(template).any(|caps| &caps[1] == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_without_stars() {
+        assert!(matches("abc123/a", "abc123/a"));
+        assert!(!matches("abc123/a", "abc123/ab"));
+        assert!(!matches("abc123/a", "abc123"));
+        assert!(matches("", ""));
+        assert!(!matches("", "a"));
+    }
+
+    #[test]
+    fn matches_stars_at_either_end() {
+        assert!(matches("abc*", "abc123/a"));
+        assert!(matches("abc*", "abc"));
+        assert!(!matches("abc*", "arc123/a"));
+        assert!(matches("*/a", "abc123/a"));
+        assert!(!matches("*/a", "abc123/b"));
+        assert!(matches("*", ""));
+        assert!(matches("*123*", "abc123/a"));
+        assert!(!matches("*124*", "abc123/a"));
+    }
+
+    #[test]
+    fn matches_repeated_parts() {
+        assert!(matches("a*a", "aa"));
+        assert!(!matches("a*a", "a"));
+        assert!(matches("*a*a", "aXa"));
+        assert!(!matches("*a*a", "a"));
+        assert!(matches("ab*b*b", "abbb"));
+        assert!(!matches("ab*b*b", "abb"));
+        assert!(matches("a**b", "ab"));
+        assert!(matches("*1*1*", "abc101/a"));
+        assert!(!matches("*1*1*", "abc100/a"));
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{Context as _, Result};
+use console::Style;
+use serde::Serialize;
+use structopt::StructOpt;
+
+use crate::{
+    atcoder::{AtCoder, RatingHistoryEntry, UserProfile},
+    session_file,
+};
+
+#[derive(StructOpt)]
+pub struct UserOpt {
+    /// User name (default: the logged-in user)
+    name: Option<String>,
+    /// Output in JSON
+    #[structopt(long)]
+    json: bool,
+}
+
+#[derive(Serialize)]
+struct UserInfo {
+    #[serde(flatten)]
+    profile: UserProfile,
+    color: Option<&'static str>,
+    history: Vec<RatingHistoryEntry>,
+}
+
+pub async fn user(opt: UserOpt) -> Result<()> {
+    let atc = AtCoder::new(&session_file()?)?;
+    let name = match opt.name {
+        Some(name) => name,
+        None => atc
+            .username()
+            .await?
+            .with_context(|| "You are not logged in. Please specify a user name.")?,
+    };
+
+    let profile = atc.user_profile(&name).await?;
+    let history = atc
+        .rating_history(&name)
+        .await?
+        .into_iter()
+        .filter(|e| e.is_rated)
+        .collect::<Vec<_>>();
+
+    if opt.json {
+        let info = UserInfo {
+            color: profile.rating.map(color_name),
+            profile,
+            history,
+        };
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+
+    let rating = |r: Option<i64>| match r {
+        Some(r) => format!("{}", color_style(r).apply_to(r)),
+        None => "N/A".to_owned(),
+    };
+
+    println!("User:           {}", profile.name);
+    println!("Rating:         {}", rating(profile.rating));
+    println!("Highest rating: {}", rating(profile.highest_rating));
+    println!(
+        "Rank:           {}",
+        profile
+            .rank
+            .map_or_else(|| "N/A".to_owned(), |r| r.to_string())
+    );
+    println!(
+        "Color:          {}",
+        profile.rating.map_or("N/A", color_name)
+    );
+    println!("Rated matches:  {}", profile.competitions);
+
+    if !history.is_empty() {
+        println!();
+        print_chart(&history.iter().map(|e| e.new_rating).collect::<Vec<_>>());
+        println!();
+        println!("History:");
+        for e in &history {
+            println!(
+                "    {} | {:>5} | {:>5} | {:>5} -> {:>5} ({:+}) | {}",
+                e.end_time.format("%Y-%m-%d"),
+                e.place,
+                e.performance,
+                e.old_rating,
+                e.new_rating,
+                e.new_rating - e.old_rating,
+                e.contest_name,
+            );
+        }
+    }
+    Ok(())
+}
+
+fn color_name(rating: i64) -> &'static str {
+    match rating {
+        r if r < 400 => "gray",
+        r if r < 800 => "brown",
+        r if r < 1200 => "green",
+        r if r < 1600 => "cyan",
+        r if r < 2000 => "blue",
+        r if r < 2400 => "yellow",
+        r if r < 2800 => "orange",
+        _ => "red",
+    }
+}
+
+fn color_style(rating: i64) -> Style {
+    match color_name(rating) {
+        "gray" => Style::new().color256(8),
+        "brown" => Style::new().color256(130),
+        "green" => Style::new().green(),
+        "cyan" => Style::new().cyan(),
+        "blue" => Style::new().blue(),
+        "yellow" => Style::new().yellow(),
+        "orange" => Style::new().color256(208),
+        _ => Style::new().red(),
+    }
+}
+
+/// Plots the ratings, keeping at most `WIDTH` most recent points.
+fn print_chart(ratings: &[i64]) {
+    const HEIGHT: usize = 10;
+    const WIDTH: usize = 60;
+
+    let ratings = &ratings[ratings.len().saturating_sub(WIDTH)..];
+    let min = *ratings.iter().min().unwrap();
+    let max = *ratings.iter().max().unwrap();
+    let row_of = |r: i64| -> usize {
+        if max == min {
+            0
+        } else {
+            ((r - min) as f64 / (max - min) as f64 * (HEIGHT - 1) as f64).round() as usize
+        }
+    };
+
+    for row in (0..HEIGHT).rev() {
+        let label = min + (max - min) * row as i64 / (HEIGHT - 1) as i64;
+        let line = ratings
+            .iter()
+            .map(|&r| {
+                if row_of(r) == row {
+                    format!("{}", color_style(r).apply_to('*'))
+                } else {
+                    " ".to_owned()
+                }
+            })
+            .collect::<String>();
+        println!("{:>5} | {}", label, line);
+    }
+    println!("      +{}", "-".repeat(ratings.len()));
+}
@@ -14,8 +14,10 @@ use structopt::StructOpt;
 
 use crate::{
     atcoder::AtCoder,
+    clarification::{format_clarification, SeenClarifications},
+    embedded_samples, fetch_samples, find_problem,
     metadata::{self, MetadataExt, PackageExt},
-    session_file, test_samples,
+    session_file, test_samples, CLARIFICATION_INTERVAL,
 };
 
 // use termion::raw::IntoRawMode;
@@ -54,6 +56,7 @@ pub async fn watch(opt: WatchOpt) -> Result<()> {
     let cwd = env::current_dir().with_context(|| "failed to get CWD")?;
     let metadata = metadata::cargo_metadata(opt.manifest_path.as_deref(), &cwd)?;
     let package = metadata.query_for_member(opt.package.as_deref())?.clone();
+    let contest_id = package.contest_id()?;
     let atc = AtCoder::new(&session_file()?)?;
 
     let atc = Arc::new(atc);
@@ -69,6 +72,16 @@ pub async fn watch(opt: WatchOpt) -> Result<()> {
         tokio::spawn(async move { watch_filesystem(&package, &atc).await })
     };
 
+    // Not selected below: failing to watch clarifications should not stop `watch`.
+    {
+        let atc = atc.clone();
+        tokio::spawn(async move {
+            if let Err(err) = watch_clarifications(&atc, &contest_id).await {
+                eprintln!("Failed to watch clarifications: {:?}", err);
+            }
+        });
+    }
+
     // let ui_fut = {
     //     tokio::spawn(async move {
     //         for ev in io::stdin().events() {
@@ -92,10 +105,27 @@ pub async fn watch(opt: WatchOpt) -> Result<()> {
     Ok(())
 }
 
+async fn watch_clarifications(atc: &AtCoder, contest_id: &str) -> Result<()> {
+    let mut seen = SeenClarifications::load(contest_id)?;
+
+    loop {
+        match seen.take_new(atc, contest_id).await {
+            Ok(new) => {
+                for line in new.iter().flat_map(format_clarification) {
+                    println!("{}", line);
+                }
+            }
+            Err(err) => eprintln!("Failed to fetch clarifications: {}", err),
+        }
+        tokio::time::sleep(CLARIFICATION_INTERVAL).await;
+    }
+}
+
 async fn watch_filesystem(package: &Package, atc: &AtCoder) -> Result<()> {
     use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
 
-    let contest_info = atc.contest_info(&package.name).await?;
+    let contest_id = package.contest_id()?;
+    let contest_info = atc.contest_info(&contest_id).await?;
 
     let (tx, rx) = channel();
     let mut watcher: RecommendedWatcher = Watcher::new(tx, Duration::from_millis(150))?;
@@ -127,11 +157,12 @@ async fn watch_filesystem(package: &Package, atc: &AtCoder) -> Result<()> {
 
         let problem_id = pb.file_stem().unwrap().to_string_lossy().into_owned();
 
-        let problem = if let Some(problem) = contest_info.problem(&problem_id) {
-            problem
-        } else {
-            eprintln!("Problem `{}` is not contained in this contest", &problem_id);
-            continue;
+        let problem = match find_problem(package, &contest_id, &contest_info, &problem_id) {
+            Ok(problem) => problem,
+            Err(err) => {
+                eprintln!("{}", err);
+                continue;
+            }
         };
 
         let source = fs::read(&pb).with_context(|| format!("Failed to read {}", pb.display()))?;
@@ -143,7 +174,10 @@ async fn watch_filesystem(package: &Package, atc: &AtCoder) -> Result<()> {
 
         file_hash.insert(problem_id.clone(), hash);
 
-        let test_cases = atc.test_cases(&problem.url).await?;
+        let test_cases = match embedded_samples(package, &problem_id) {
+            Some(test_cases) => test_cases,
+            None => fetch_samples(atc, problem).await?,
+        };
         let test_cases = test_cases.into_iter().enumerate().collect::<Vec<_>>();
         let test_passed = test_samples(package, &problem_id, &test_cases, false, false)?;
 
@@ -0,0 +1,84 @@
+use std::path::Path;
+use std::str;
+use std::time::Duration;
+use tempdir::TempDir;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[test]
+fn not_logged_in() -> anyhow::Result<()> {
+    let tempdir = TempDir::new("cargo-atcoder-test-errors-not-logged-in")?;
+    cargo_atcoder_new(tempdir.path())?;
+
+    let assert = cargo_atcoder(tempdir.path(), &["submit", "practicea"])?
+        .assert()
+        .code(3);
+    assert!(str::from_utf8(&assert.get_output().stderr)?.contains("You are not logged in"));
+
+    tempdir.close().map_err(Into::into)
+}
+
+#[test]
+fn problem_not_found() -> anyhow::Result<()> {
+    let tempdir = TempDir::new("cargo-atcoder-test-errors-problem-not-found")?;
+    cargo_atcoder_new(tempdir.path())?;
+
+    let assert = cargo_atcoder(tempdir.path(), &["test", "practicez"])?
+        .assert()
+        .code(6);
+    assert!(str::from_utf8(&assert.get_output().stderr)?
+        .contains("Problem `practicez` is not contained in `language-test-202001`"));
+
+    tempdir.close().map_err(Into::into)
+}
+
+#[test]
+fn user_not_found() -> anyhow::Result<()> {
+    let tempdir = TempDir::new("cargo-atcoder-test-errors-user-not-found")?;
+
+    let assert = assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "user", "no-such-user-cargo-atcoder"])
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", tempdir.path())
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", tempdir.path())
+        .timeout(TIMEOUT)
+        .assert()
+        .failure();
+    let stderr = str::from_utf8(&assert.get_output().stderr)?;
+    assert!(stderr.contains("User `no-such-user-cargo-atcoder` does not exist"));
+    assert!(!stderr.contains("You are not logged in"));
+
+    tempdir.close().map_err(Into::into)
+}
+
+fn cargo_atcoder_new(dir: &Path) -> anyhow::Result<()> {
+    assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args([
+            "atcoder",
+            "new",
+            "language-test-202001",
+            "--skip-warmup",
+            "-b",
+            "practicea",
+            "practicez",
+        ])
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", dir)
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", dir)
+        .current_dir(dir)
+        .timeout(TIMEOUT)
+        .assert()
+        .success();
+    Ok(())
+}
+
+fn cargo_atcoder(dir: &Path, args: &[&str]) -> anyhow::Result<assert_cmd::Command> {
+    let mut cmd = assert_cmd::Command::cargo_bin("cargo-atcoder")?;
+    cmd.arg("atcoder")
+        .args(args)
+        .arg("--manifest-path")
+        .arg(dir.join("language-test-202001").join("Cargo.toml"))
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", dir)
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", dir)
+        .current_dir(dir)
+        .timeout(TIMEOUT);
+    Ok(cmd)
+}
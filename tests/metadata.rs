@@ -0,0 +1,90 @@
+use std::path::Path;
+use std::time::Duration;
+use std::{fs, str};
+use tempdir::TempDir;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[test]
+fn contest_and_task() -> anyhow::Result<()> {
+    let tempdir = TempDir::new("cargo-atcoder-test-metadata-contest-and-task")?;
+    let project_dir = tempdir.path().join("upsolve");
+
+    cargo_atcoder_new(tempdir.path(), &["next-month", "b", "z"])?;
+
+    let manifest_path = project_dir.join("Cargo.toml");
+    let manifest = fs::read_to_string(&manifest_path)?;
+    fs::write(
+        &manifest_path,
+        format!(
+            r#"{}
+[package.metadata.cargo-atcoder]
+contest = "abc011"
+
+[package.metadata.cargo-atcoder.bin.next-month]
+task = "abc011_1"
+
+[package.metadata.cargo-atcoder.bin.b]
+task = "abc011_9"
+"#,
+            manifest,
+        ),
+    )?;
+    fs::write(
+        project_dir.join("src").join("bin").join("next-month.rs"),
+        r#"use std::io::{self, Read as _};
+
+fn main() {
+    let mut input = "".to_owned();
+    io::stdin().read_to_string(&mut input).unwrap();
+    let n = input.trim().parse::<u32>().unwrap();
+    println!("{}", n % 12 + 1);
+}
+"#,
+    )?;
+
+    let assert = cargo_atcoder_test(tempdir.path(), "next-month")?
+        .assert()
+        .success();
+    assert_eq!(
+        str::from_utf8(&assert.get_output().stdout)?,
+        "running 2 tests\ntest sample 1 ... ok\ntest sample 2 ... ok\n\ntest_result: ok\n\n",
+    );
+
+    // A configured task that does not exist.
+    let assert = cargo_atcoder_test(tempdir.path(), "b")?.assert().code(6);
+    assert!(str::from_utf8(&assert.get_output().stderr)?
+        .contains("Problem `abc011_9` is not contained in `abc011`"));
+
+    // A bin that matches no problem.
+    let assert = cargo_atcoder_test(tempdir.path(), "z")?.assert().code(6);
+    assert!(str::from_utf8(&assert.get_output().stderr)?
+        .contains("Problem `z` is not contained in `abc011`"));
+
+    tempdir.close().map_err(Into::into)
+}
+
+fn cargo_atcoder_new(dir: &Path, bins: &[&str]) -> anyhow::Result<()> {
+    assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "new", "upsolve", "--skip-warmup", "-b"])
+        .args(bins)
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", dir)
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", dir)
+        .current_dir(dir)
+        .timeout(TIMEOUT)
+        .assert()
+        .success();
+    Ok(())
+}
+
+fn cargo_atcoder_test(dir: &Path, bin: &str) -> anyhow::Result<assert_cmd::Command> {
+    let mut cmd = assert_cmd::Command::cargo_bin("cargo-atcoder")?;
+    cmd.args(["atcoder", "test", "--manifest-path"])
+        .arg(dir.join("upsolve").join("Cargo.toml"))
+        .arg(bin)
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", dir)
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", dir)
+        .current_dir(dir)
+        .timeout(TIMEOUT);
+    Ok(cmd)
+}
@@ -2,11 +2,14 @@ use cargo_metadata::{Metadata, MetadataCommand, Package};
 use maplit::btreemap;
 use pretty_assertions::assert_eq;
 use std::collections::BTreeMap;
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tempdir::TempDir;
 
 const TIMEOUT: Duration = Duration::from_secs(10);
+/// `init` builds the dependencies for debug, release and `profile.target`.
+const INIT_TIMEOUT: Duration = Duration::from_secs(60);
 
 #[test]
 fn default() -> anyhow::Result<()> {
@@ -114,6 +117,228 @@ fn bins() -> anyhow::Result<()> {
     tempdir.close().map_err(Into::into)
 }
 
+#[test]
+fn add() -> anyhow::Result<()> {
+    let tempdir = TempDir::new("cargo-atcoder-test-new-add")?;
+
+    assert_no_manifest(tempdir.path());
+
+    fs::write(
+        tempdir.path().join("Cargo.toml"),
+        "[workspace]\nmembers = []\n",
+    )?;
+
+    assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "add", "abc999", "--bins", "x", "y"])
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", tempdir.path())
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", tempdir.path())
+        .current_dir(tempdir.path())
+        .timeout(TIMEOUT)
+        .assert()
+        .success();
+
+    let metadata = cargo_metadata(&tempdir.path().join("Cargo.toml"), true)?;
+
+    assert_eq!(tempdir.path(), metadata.workspace_root);
+    assert!(!tempdir.path().join("abc999").join(".git").exists());
+    assert_build_cache_exists(metadata.workspace_root.as_ref());
+    assert_bin_names(
+        find_member(&metadata, "abc999"),
+        &btreemap!(
+            "x" => Path::new("src").join("bin").join("x.rs"),
+            "y" => Path::new("src").join("bin").join("y.rs"),
+        ),
+    );
+
+    let manifest = fs::read_to_string(tempdir.path().join("Cargo.toml"))?.parse::<toml::Value>()?;
+    assert_eq!(
+        manifest["workspace"]["members"],
+        toml::Value::Array(vec!["abc999".into()]),
+    );
+    assert!(manifest["profile"]["release"].is_table());
+
+    tempdir.close().map_err(Into::into)
+}
+
+#[test]
+fn init() -> anyhow::Result<()> {
+    let tempdir = TempDir::new("cargo-atcoder-test-new-init")?;
+    let root = tempdir.path().join("contests");
+
+    assert_no_manifest(tempdir.path());
+    write_config_with_target(tempdir.path(), &host_target()?)?;
+
+    assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "init", "contests"])
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", tempdir.path())
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", tempdir.path())
+        .current_dir(tempdir.path())
+        .timeout(INIT_TIMEOUT)
+        .assert()
+        .success();
+
+    let cargo_config =
+        fs::read_to_string(root.join(".cargo").join("config.toml"))?.parse::<toml::Value>()?;
+    assert_eq!(cargo_config["build"]["target-dir"].as_str(), Some("target"));
+    assert!(!root.join(".prebuild").exists());
+    assert!(!root.join("rust-toolchain").exists());
+    assert_build_cache_exists(&root);
+
+    let template = "// template\nfn main() {}\n";
+    fs::write(root.join("templates").join("main.rs"), template)?;
+
+    assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "add", "abc999", "--skip-warmup", "--bins", "x"])
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", tempdir.path())
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", tempdir.path())
+        .current_dir(&root)
+        .timeout(TIMEOUT)
+        .assert()
+        .success();
+
+    let metadata = cargo_metadata(&root.join("Cargo.toml"), false)?;
+    assert_eq!(
+        metadata.workspace_members,
+        [find_member(&metadata, "abc999").id.clone()],
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("abc999").join("src").join("bin").join("x.rs"))?,
+        template,
+    );
+
+    tempdir.close().map_err(Into::into)
+}
+
+#[test]
+fn init_prebuild_failure() -> anyhow::Result<()> {
+    let tempdir = TempDir::new("cargo-atcoder-test-new-init-prebuild-failure")?;
+    let root = tempdir.path().join("contests");
+
+    write_config_with_target(tempdir.path(), "no-such-target")?;
+
+    let assert = assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "init", "contests"])
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", tempdir.path())
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", tempdir.path())
+        .current_dir(tempdir.path())
+        .timeout(INIT_TIMEOUT)
+        .assert()
+        .failure();
+    assert!(std::str::from_utf8(&assert.get_output().stderr)?
+        .contains("Failed to pre-build the dependencies for no-such-target"));
+    assert!(!std::str::from_utf8(&assert.get_output().stdout)?.contains("Warming up done."));
+
+    assert!(!root.join(".prebuild").exists());
+    assert!(!fs::read_to_string(root.join("Cargo.toml"))?.contains(".prebuild"));
+
+    tempdir.close().map_err(Into::into)
+}
+
+#[test]
+fn templates() -> anyhow::Result<()> {
+    let tempdir = TempDir::new("cargo-atcoder-test-new-templates")?;
+
+    assert_no_manifest(tempdir.path());
+
+    fs::write(
+        tempdir.path().join("cargo-atcoder.toml"),
+        include_str!("../config/cargo-atcoder.toml").replace(
+            "# template_dir = \"cargo-atcoder-templates\"",
+            "template_dir = \"templates\"\n\n\
+             [[project.template_overrides]]\n\
+             pattern = \"ahc*/b\"\n\
+             template = \"b.rs\"",
+        ),
+    )?;
+    let templates = tempdir.path().join("templates");
+    fs::create_dir_all(templates.join("files"))?;
+    fs::create_dir_all(templates.join("ahc").join("files").join("src").join("bin"))?;
+    fs::write(
+        templates.join("main.rs"),
+        "// {{ contest_id }} {{problem_id}} {{ unknown }}\n",
+    )?;
+    fs::write(templates.join("b.rs"), "// b of {{ contest_type }}\n")?;
+    fs::write(
+        templates.join("files").join("NOTE.md"),
+        "# {{ contest_id }}\n",
+    )?;
+    fs::write(templates.join("ahc").join("main.rs"), "// heuristic\n")?;
+    fs::write(
+        templates
+            .join("ahc")
+            .join("files")
+            .join("src")
+            .join("bin")
+            .join("gen.rs"),
+        "fn main() {}\n",
+    )?;
+
+    for contest_id in &["abc999", "ahc999"] {
+        assert_cmd::Command::cargo_bin("cargo-atcoder")?
+            .args([
+                "atcoder",
+                "new",
+                contest_id,
+                "--skip-warmup",
+                "-b",
+                "a",
+                "b",
+            ])
+            .env("CARGO_ATCODER_TEST_CONFIG_DIR", tempdir.path())
+            .env("CARGO_ATCODER_TEST_CACHE_DIR", tempdir.path())
+            .current_dir(tempdir.path())
+            .timeout(TIMEOUT)
+            .assert()
+            .success();
+    }
+
+    let read = |path: &[&str]| {
+        fs::read_to_string(
+            path.iter()
+                .fold(tempdir.path().to_owned(), |p, c| p.join(c)),
+        )
+    };
+    assert_eq!(
+        read(&["abc999", "src", "bin", "a.rs"])?,
+        "// abc999 a {{ unknown }}\n",
+    );
+    assert_eq!(
+        read(&["abc999", "src", "bin", "b.rs"])?,
+        "// abc999 b {{ unknown }}\n",
+    );
+    assert_eq!(read(&["abc999", "NOTE.md"])?, "# abc999\n");
+    assert!(!tempdir
+        .path()
+        .join("abc999")
+        .join("src")
+        .join("bin")
+        .join("gen.rs")
+        .exists());
+    assert_eq!(read(&["ahc999", "src", "bin", "a.rs"])?, "// heuristic\n");
+    assert_eq!(read(&["ahc999", "src", "bin", "b.rs"])?, "// b of ahc\n");
+    assert_eq!(read(&["ahc999", "src", "bin", "gen.rs"])?, "fn main() {}\n");
+    assert_eq!(read(&["ahc999", "NOTE.md"])?, "# ahc999\n");
+
+    // An extra file must not overwrite a generated one.
+    let assert = assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "new", "ahc998", "--skip-warmup", "-b", "gen"])
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", tempdir.path())
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", tempdir.path())
+        .current_dir(tempdir.path())
+        .timeout(TIMEOUT)
+        .assert()
+        .failure();
+    assert!(
+        std::str::from_utf8(&assert.get_output().stderr)?.contains(&format!(
+            "The extra file `{}` in the templates conflicts",
+            Path::new("src").join("bin").join("gen.rs").display(),
+        ))
+    );
+    assert!(!tempdir.path().join("ahc998").exists());
+
+    tempdir.close().map_err(Into::into)
+}
+
 fn assert_no_manifest(dir: &Path) {
     if let Some(manifest_dir) = dir.ancestors().find(|p| p.join("Cargo.toml").exists()) {
         panic!("found Cargo.toml at {}", manifest_dir.display());
@@ -140,6 +365,28 @@ fn assert_is_git_root(workspace_root: &Path) {
     assert!(workspace_root.join(".git").is_dir());
 }
 
+/// Writes the default config with `profile.target`, since the default target may not be
+/// installed.
+fn write_config_with_target(config_dir: &Path, target: &str) -> anyhow::Result<()> {
+    fs::write(
+        config_dir.join("cargo-atcoder.toml"),
+        include_str!("../config/cargo-atcoder.toml").replace(
+            "target = \"x86_64-unknown-linux-musl\"",
+            &format!("target = {:?}", target),
+        ),
+    )?;
+    Ok(())
+}
+
+fn host_target() -> anyhow::Result<String> {
+    let output = std::process::Command::new("rustc").arg("-vV").output()?;
+    String::from_utf8(output.stdout)?
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| anyhow::anyhow!("`rustc -vV` does not show the host"))
+}
+
 fn assert_build_cache_exists(workspace_root: &Path) {
     assert!(workspace_root.join("target").join("debug").is_dir());
     assert!(workspace_root.join("target").join("release").is_dir());
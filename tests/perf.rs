@@ -0,0 +1,84 @@
+use std::path::Path;
+use std::time::Duration;
+use std::{fs, str};
+use tempdir::TempDir;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test]
+fn from_local_files() -> anyhow::Result<()> {
+    let tempdir = TempDir::new("cargo-atcoder-test-perf-from-local-files")?;
+
+    assert_no_manifest(tempdir.path());
+    cargo_atcoder_new(tempdir.path())?;
+
+    let standings = tempdir.path().join("standings.json");
+    let ratings = tempdir.path().join("ratings.json");
+    fs::write(&standings, STANDINGS)?;
+    fs::write(&ratings, r#"{"alice": 1200.0, "bob": 1200.0}"#)?;
+
+    let assert = assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "perf", "--standings"])
+        .arg(&standings)
+        .arg("--ratings")
+        .arg(&ratings)
+        .arg("--manifest-path")
+        .arg(tempdir.path().join("agc999").join("Cargo.toml"))
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", tempdir.path())
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", tempdir.path())
+        .current_dir(tempdir.path())
+        .timeout(TIMEOUT)
+        .assert()
+        .success();
+
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let lines = stdout.lines().skip(1).collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "    1 | alice                |  1445 |      0 |    272 |  +272",
+            "    2 | bob                  |   955 |      0 |     80 |   +80",
+        ],
+    );
+
+    tempdir.close().map_err(Into::into)
+}
+
+fn assert_no_manifest(dir: &Path) {
+    if let Some(manifest_dir) = dir.ancestors().find(|p| p.join("Cargo.toml").exists()) {
+        panic!("found Cargo.toml at {}", manifest_dir.display());
+    }
+}
+
+fn cargo_atcoder_new(dir: &Path) -> anyhow::Result<()> {
+    assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "new", "agc999", "--skip-warmup", "-b", "a"])
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", dir)
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", dir)
+        .current_dir(dir)
+        .timeout(TIMEOUT)
+        .assert()
+        .success();
+    Ok(())
+}
+
+static STANDINGS: &str = r#"{
+  "TaskInfo": [{"Assignment": "A", "TaskName": "A", "TaskScreenName": "agc999_a"}],
+  "StandingsData": [
+    {
+      "Rank": 1, "UserScreenName": "alice", "OldRating": 0, "IsRated": true, "Competitions": 0,
+      "TaskResults": {"agc999_a": {"Count": 1, "Failure": 0, "Penalty": 0, "Score": 30000, "Elapsed": 600000000000, "Pending": false}},
+      "TotalResult": {"Count": 1, "Penalty": 0, "Score": 30000, "Elapsed": 600000000000}
+    },
+    {
+      "Rank": 2, "UserScreenName": "bob", "OldRating": 0, "IsRated": true, "Competitions": 0,
+      "TaskResults": {"agc999_a": {"Count": 2, "Failure": 2, "Penalty": 2, "Score": 0, "Elapsed": 0, "Pending": false}},
+      "TotalResult": {"Count": 2, "Penalty": 2, "Score": 0, "Elapsed": 0}
+    },
+    {
+      "Rank": 3, "UserScreenName": "carol", "OldRating": 1500, "IsRated": true, "Competitions": 10,
+      "TaskResults": {},
+      "TotalResult": {"Count": 0, "Penalty": 0, "Score": 0, "Elapsed": 0}
+    }
+  ]
+}"#;
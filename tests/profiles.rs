@@ -0,0 +1,56 @@
+use std::time::Duration;
+use std::{fs, str};
+use tempdir::TempDir;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test]
+fn list() -> anyhow::Result<()> {
+    let tempdir = TempDir::new("cargo-atcoder-test-profiles-list")?;
+
+    fs::write(
+        tempdir.path().join("cargo-atcoder.toml"),
+        format!(
+            "{}\n[profiles.practice.atcoder]\nlanguage = \"C++\"\n",
+            include_str!("../config/cargo-atcoder.toml"),
+        ),
+    )?;
+
+    let assert = assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "--profile", "main", "profiles"])
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", tempdir.path())
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", tempdir.path())
+        .env_remove("CARGO_ATCODER_PROFILE")
+        .current_dir(tempdir.path())
+        .timeout(TIMEOUT)
+        .assert()
+        .success();
+
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        [
+            "  default          | (not logged in)",
+            "* main             | (not logged in)",
+            "  practice         | (not logged in)",
+        ],
+    );
+
+    tempdir.close().map_err(Into::into)
+}
+
+#[test]
+fn invalid_name() -> anyhow::Result<()> {
+    let tempdir = TempDir::new("cargo-atcoder-test-profiles-invalid-name")?;
+
+    assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "--profile", "../main", "profiles"])
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", tempdir.path())
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", tempdir.path())
+        .current_dir(tempdir.path())
+        .timeout(TIMEOUT)
+        .assert()
+        .failure();
+
+    tempdir.close().map_err(Into::into)
+}
@@ -0,0 +1,224 @@
+//! Scrapes sample test cases from the live pages of older contests, whose layouts differ
+//! from the current one, and reads them back from generated doc comments. The layouts
+//! themselves are covered offline by the unit tests in `src/atcoder.rs`.
+
+use std::time::Duration;
+use std::{fs, str};
+use tempdir::TempDir;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[test]
+fn abc001_section_per_sample() -> anyhow::Result<()> {
+    check(
+        "abc001",
+        3,
+        r#"fn main() {
+    let t = tokens();
+    let (h1, h2) = (t[0].parse::<i32>().unwrap(), t[1].parse::<i32>().unwrap());
+    println!("{}", h1 - h2);
+}
+"#,
+    )
+}
+
+#[test]
+fn abc011_samples_in_one_part() -> anyhow::Result<()> {
+    check(
+        "abc011",
+        2,
+        r#"fn main() {
+    let n = tokens()[0].parse::<u32>().unwrap();
+    println!("{}", n % 12 + 1);
+}
+"#,
+    )
+}
+
+#[test]
+fn abc042_bilingual() -> anyhow::Result<()> {
+    check(
+        "abc042",
+        2,
+        r#"fn main() {
+    let mut abc = tokens();
+    abc.sort();
+    println!("{}", if abc == ["5", "5", "7"] { "YES" } else { "NO" });
+}
+"#,
+    )
+}
+
+#[test]
+fn arc001_labels_in_paragraphs() -> anyhow::Result<()> {
+    check(
+        "arc001",
+        3,
+        r#"fn main() {
+    let c = tokens().pop().unwrap();
+    let counts = (b'1'..=b'4')
+        .map(|d| c.bytes().filter(|&b| b == d).count())
+        .collect::<Vec<_>>();
+    println!(
+        "{} {}",
+        counts.iter().max().unwrap(),
+        counts.iter().min().unwrap(),
+    );
+}
+"#,
+    )
+}
+
+#[test]
+fn joi2008yo_labels_in_text() -> anyhow::Result<()> {
+    check(
+        "joi2008yo",
+        2,
+        r#"fn main() {
+    let mut change = 1000 - tokens()[0].parse::<u32>().unwrap();
+    let mut coins = 0;
+    for &coin in &[500, 100, 50, 10, 5, 1] {
+        coins += change / coin;
+        change %= coin;
+    }
+    println!("{}", coins);
+}
+"#,
+    )
+}
+
+#[test]
+fn abc001_doc_comment() -> anyhow::Result<()> {
+    let tempdir = TempDir::new("cargo-atcoder-test-samples-doc-comment")?;
+
+    assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "new", "abc001", "--doc-comment", "--skip-warmup"])
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", tempdir.path())
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", tempdir.path())
+        .current_dir(tempdir.path())
+        .timeout(TIMEOUT)
+        .assert()
+        .success();
+
+    let project_dir = tempdir.path().join("abc001");
+    let src_path = project_dir.join("src").join("bin").join("a.rs");
+    let source = fs::read_to_string(&src_path)?;
+    assert!(source.starts_with(
+        "//! # A - 積雪深差\n//!\n//! <https://atcoder.jp/contests/abc001/tasks/abc001_1>\n",
+    ));
+    assert!(source.contains("//! ## 入力例 3\n//!\n//! ```text\n//! 5\n//! 20\n//! ```\n"));
+    assert!(source.contains("//! ## 出力例 3\n//!\n//! ```text\n//! -15\n//! ```\n"));
+
+    // A sample added by hand, so that the test shows where the samples come from.
+    let doc_comment = source
+        .lines()
+        .take_while(|line| line.starts_with("//!"))
+        .map(|line| format!("{}\n", line))
+        .chain(
+            [
+                "//!",
+                "//! ## 入力例 4",
+                "//!",
+                "//! ```text",
+                "//! 10",
+                "//! 3",
+                "//! ```",
+                "//!",
+                "//! ## 出力例 4",
+                "//!",
+                "//! ```text",
+                "//! 7",
+                "//! ```",
+            ]
+            .iter()
+            .map(|line| format!("{}\n", line)),
+        )
+        .collect::<String>();
+    fs::write(
+        &src_path,
+        format!(
+            r#"{}
+{}fn main() {{
+    let t = tokens();
+    let (h1, h2) = (t[0].parse::<i32>().unwrap(), t[1].parse::<i32>().unwrap());
+    println!("{{}}", h1 - h2);
+}}
+"#,
+            doc_comment, PRELUDE,
+        ),
+    )?;
+
+    let assert = assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "test", "--manifest-path"])
+        .arg(project_dir.join("Cargo.toml"))
+        .arg("a")
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", tempdir.path())
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", tempdir.path())
+        .current_dir(tempdir.path())
+        .timeout(TIMEOUT)
+        .assert()
+        .success();
+    assert!(str::from_utf8(&assert.get_output().stderr)?
+        .contains("Using the samples in the doc comment of"));
+    assert_eq!(
+        str::from_utf8(&assert.get_output().stdout)?,
+        "running 4 tests\ntest sample 1 ... ok\ntest sample 2 ... ok\ntest sample 3 ... ok\ntest sample 4 ... ok\n\ntest_result: ok\n\n",
+    );
+
+    tempdir.close().map_err(Into::into)
+}
+
+/// Prepended to each solution.
+static PRELUDE: &str = r#"use std::io::{self, Read as _};
+
+fn tokens() -> Vec<String> {
+    let mut input = "".to_owned();
+    io::stdin().read_to_string(&mut input).unwrap();
+    input.split_ascii_whitespace().map(ToOwned::to_owned).collect()
+}
+
+"#;
+
+fn check(contest_id: &str, num_samples: usize, solution: &str) -> anyhow::Result<()> {
+    let tempdir = TempDir::new(&format!("cargo-atcoder-test-samples-{}", contest_id))?;
+
+    assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "new", contest_id, "--skip-warmup", "-b", "a"])
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", tempdir.path())
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", tempdir.path())
+        .current_dir(tempdir.path())
+        .timeout(TIMEOUT)
+        .assert()
+        .success();
+
+    let project_dir = tempdir.path().join(contest_id);
+    fs::write(
+        project_dir.join("src").join("bin").join("a.rs"),
+        format!("{}{}", PRELUDE, solution),
+    )?;
+
+    let assert = assert_cmd::Command::cargo_bin("cargo-atcoder")?
+        .args(["atcoder", "test", "--manifest-path"])
+        .arg(project_dir.join("Cargo.toml"))
+        .arg("a")
+        .env("CARGO_ATCODER_TEST_CONFIG_DIR", tempdir.path())
+        .env("CARGO_ATCODER_TEST_CACHE_DIR", tempdir.path())
+        .current_dir(tempdir.path())
+        .timeout(TIMEOUT)
+        .assert()
+        .success();
+
+    let stdout = str::from_utf8(&assert.get_output().stdout)?;
+    let expected = (1..=num_samples)
+        .map(|i| format!("test sample {} ... ok\n", i))
+        .collect::<String>();
+    assert_eq!(
+        stdout,
+        format!(
+            "running {} tests\n{}\ntest_result: ok\n\n",
+            num_samples, expected
+        ),
+    );
+
+    tempdir.close().map_err(Into::into)
+}